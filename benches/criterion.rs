@@ -26,6 +26,7 @@ pub fn criterion_benchmark(c: &mut Criterion) {
         (1000000, 100, 20),
         (1000000, 100, 50),
         (1000000, 100, 100),
+        (1000000, 1, 4096),
         (1000000, 5, 4096),
         (1000000, 20, 4096),
         (1000000, 50, 4096),
@@ -37,8 +38,19 @@ pub fn criterion_benchmark(c: &mut Criterion) {
             |b| {
                 b.iter(|| {
                     let reader = Cursor::new(input(black_box(file_length), black_box(line_length)));
-                    let mut rev_lines = RawRevLines::with_capacity(buffer_capacity, reader);
-                    while let Some(_) = rev_lines.next() {}
+                    let rev_lines = RawRevLines::with_capacity(buffer_capacity, reader);
+                    for _ in rev_lines {}
+                })
+            },
+        );
+
+        c.bench_function(
+            &format!("RawRevLines::from_cursor file_length={file_length} line_length={line_length}, buffer_capacity={buffer_capacity}"),
+            |b| {
+                b.iter(|| {
+                    let reader = Cursor::new(input(black_box(file_length), black_box(line_length)));
+                    let rev_lines = RawRevLines::from_cursor_with_capacity(buffer_capacity, reader);
+                    for _ in rev_lines {}
                 })
             },
         );
@@ -22,14 +22,14 @@ fn input(file_length: usize, lines_length: u32) -> Vec<u8> {
 
 fn raw_rev_lines_next_line_length_20_buffer_capacity_4096() {
     let reader = Cursor::new(input(FILE_LENGTH, 20));
-    let mut rev_lines = RawRevLines::with_capacity(4096, reader);
-    while let Some(_) = rev_lines.next() {}
+    let rev_lines = RawRevLines::with_capacity(4096, reader);
+    for _ in rev_lines {}
 }
 
 fn raw_rev_lines_next_line_length_160_buffer_capacity_4096() {
     let reader = Cursor::new(input(FILE_LENGTH, 160));
-    let mut rev_lines = RawRevLines::with_capacity(4096, reader);
-    while let Some(_) = rev_lines.next() {}
+    let rev_lines = RawRevLines::with_capacity(4096, reader);
+    for _ in rev_lines {}
 }
 
 iai::main!(
@@ -0,0 +1,381 @@
+//! Iterators returned by the adapter methods of [`RawRevLines`] and [`RevLines`], and
+//! [`RevLinesChain`] for reading several readers as one.
+
+use std::cmp::Ordering;
+use std::io::{self, Cursor, Read, Seek};
+
+use crate::{LineDecoder, RawRevLines, RevLines, RevLinesError, Utf8Decoder};
+
+/// Iterator returned by [`RawRevLines::take_while_prefix`]
+pub struct TakeWhilePrefix<R, F> {
+    pub(crate) rev_lines: RawRevLines<R>,
+    pub(crate) pred: F,
+    pub(crate) done: bool,
+}
+
+impl<R: Read + Seek, F: Fn(&[u8]) -> bool> Iterator for TakeWhilePrefix<R, F> {
+    type Item = io::Result<Vec<u8>>;
+
+    fn next(&mut self) -> Option<io::Result<Vec<u8>>> {
+        if self.done {
+            return None;
+        }
+
+        match self.rev_lines.next()? {
+            Ok(line) if (self.pred)(&line) => Some(Ok(line)),
+            Ok(_) => {
+                self.done = true;
+                None
+            }
+            Err(error) => Some(Err(error)),
+        }
+    }
+}
+
+/// Iterator returned by [`RawRevLines::truncate_long_lines`]
+///
+/// Yields each line along with whether it was truncated.
+pub struct TruncatedLines<R>(pub(crate) RawRevLines<R>);
+
+impl<R: Read + Seek> Iterator for TruncatedLines<R> {
+    type Item = io::Result<(Vec<u8>, bool)>;
+
+    fn next(&mut self) -> Option<io::Result<(Vec<u8>, bool)>> {
+        match self.0.next_line() {
+            Ok(line) => line.map(|line| Ok((line, self.0.line_truncated))),
+            Err(error) => Some(Err(error)),
+        }
+    }
+}
+
+/// Reads several readers in reverse as if they were one, such as a set of rotated logs.
+///
+/// Readers are given in forward order, oldest first. Iteration yields the lines of the
+/// last reader in reverse, then those of the one before it, and so on. Lines never
+/// span two readers: a reader that does not end in a line feed still ends its last line.
+///
+/// ```
+/// use std::io::Cursor;
+///
+/// use rev_lines::RevLinesChain;
+///
+/// let rotated = Cursor::new("\u{feff}first\nsecond\n");
+/// let current = Cursor::new("\u{feff}third\n");
+/// let lines = RevLinesChain::new([rotated, current])
+///     .strip_bom(true)
+///     .collect::<Result<Vec<_>, _>>()
+///     .unwrap();
+///
+/// assert_eq!(lines, vec!["third", "second", "first"]);
+/// ```
+pub struct RevLinesChain<R> {
+    readers: Vec<RevLines<R>>,
+    strip_bom: bool,
+}
+
+static UTF8_BOM: &[u8] = b"\xEF\xBB\xBF";
+
+impl<R: Read + Seek> RevLinesChain<R> {
+    /// Create a new `RevLinesChain` from Readers in forward order.
+    pub fn new<I: IntoIterator<Item = R>>(readers: I) -> RevLinesChain<R> {
+        RevLinesChain {
+            readers: readers.into_iter().map(RevLines::new).collect(),
+            strip_bom: false,
+        }
+    }
+
+    /// Strip a UTF-8 byte order mark from the start of each reader. In reverse, that is
+    /// the last line yielded from each reader before moving on to the previous one.
+    pub fn strip_bom(mut self, strip: bool) -> RevLinesChain<R> {
+        self.strip_bom = strip;
+        self
+    }
+}
+
+impl<R: Read + Seek> Iterator for RevLinesChain<R> {
+    type Item = Result<String, RevLinesError>;
+
+    fn next(&mut self) -> Option<Result<String, RevLinesError>> {
+        loop {
+            let rev_lines = self.readers.last_mut()?;
+
+            match rev_lines.raw.next_line_at() {
+                Ok(Some((offset, mut line))) => {
+                    if self.strip_bom && offset == 0 && line.starts_with(UTF8_BOM) {
+                        line.drain(..UTF8_BOM.len());
+                    }
+
+                    return Some(rev_lines.decode(offset, line));
+                }
+                Ok(None) => {
+                    self.readers.pop();
+                }
+                Err(error) => return Some(Err(RevLinesError::Io(error))),
+            }
+        }
+    }
+}
+
+/// Item yielded by [`RevLines::with_bof_marker`]
+#[derive(Debug)]
+pub enum BofItem {
+    Line(Result<String, RevLinesError>),
+    /// The start of the reader was reached, after the first line in it was yielded if
+    /// there is one
+    TopReached,
+}
+
+/// Iterator returned by [`RevLines::peekable_rev`]
+pub struct PeekableRevLines<R, D = Utf8Decoder> {
+    pub(crate) rev_lines: RevLines<R, D>,
+    // A line was peeked at when this is `Some`, which may be the end of iteration
+    pub(crate) peeked: Option<Option<Result<String, RevLinesError>>>,
+}
+
+impl<R: Read + Seek, D: LineDecoder> PeekableRevLines<R, D> {
+    /// Returns the line the next call to `next` yields, without consuming it.
+    ///
+    /// ```
+    /// use std::io::Cursor;
+    ///
+    /// use rev_lines::RevLines;
+    ///
+    /// let mut rev_lines = RevLines::new(Cursor::new("first\nsecond\n")).peekable_rev();
+    ///
+    /// if let Some(Ok(line)) = rev_lines.peek() {
+    ///     assert_eq!(line, "second");
+    /// }
+    /// assert_eq!(rev_lines.next().unwrap().unwrap(), "second");
+    /// ```
+    pub fn peek(&mut self) -> Option<&Result<String, RevLinesError>> {
+        let rev_lines = &mut self.rev_lines;
+        self.peeked.get_or_insert_with(|| rev_lines.next()).as_ref()
+    }
+}
+
+impl<R: Read + Seek, D: LineDecoder> Iterator for PeekableRevLines<R, D> {
+    type Item = Result<String, RevLinesError>;
+
+    fn next(&mut self) -> Option<Result<String, RevLinesError>> {
+        match self.peeked.take() {
+            Some(peeked) => peeked,
+            None => self.rev_lines.next(),
+        }
+    }
+}
+
+/// Iterator returned by [`RevLines::merge_reversed`]
+pub struct MergeReversed<R, S, D, E, F> {
+    pub(crate) left: PeekableRevLines<R, D>,
+    pub(crate) right: PeekableRevLines<S, E>,
+    pub(crate) cmp: F,
+}
+
+impl<R, S, D, E, F> Iterator for MergeReversed<R, S, D, E, F>
+where
+    R: Read + Seek,
+    S: Read + Seek,
+    D: LineDecoder,
+    E: LineDecoder,
+    F: FnMut(&str, &str) -> Ordering,
+{
+    type Item = Result<String, RevLinesError>;
+
+    fn next(&mut self) -> Option<Result<String, RevLinesError>> {
+        let take_left = match (self.left.peek(), self.right.peek()) {
+            (None, None) => return None,
+            (Some(_), None) | (Some(Err(_)), _) => true,
+            (None, Some(_)) | (_, Some(Err(_))) => false,
+            (Some(Ok(left)), Some(Ok(right))) => (self.cmp)(left, right) != Ordering::Less,
+        };
+
+        if take_left {
+            self.left.next()
+        } else {
+            self.right.next()
+        }
+    }
+}
+
+/// The last lines of a reader in forward order, which can be refreshed as the reader
+/// grows, e.g. for a live view of a log file.
+///
+/// ```
+/// use std::io::Cursor;
+///
+/// use rev_lines::TailWindow;
+///
+/// let mut window = TailWindow::new(Cursor::new(b"A\nB\nC\n".to_vec()), 2);
+/// assert_eq!(window.refresh().unwrap(), ["B", "C"]);
+///
+/// window.get_mut().get_mut().extend_from_slice(b"D\n");
+/// assert_eq!(window.refresh().unwrap(), ["C", "D"]);
+/// ```
+pub struct TailWindow<R> {
+    raw: RawRevLines<R>,
+    len: usize,
+    lines: Vec<String>,
+    line: Vec<u8>,
+}
+
+impl<R: Read + Seek> TailWindow<R> {
+    /// Create a new `TailWindow` holding up to `len` lines of a reader.
+    pub fn new(reader: R, len: usize) -> TailWindow<R> {
+        TailWindow {
+            raw: RawRevLines::new(reader),
+            len,
+            lines: Vec::with_capacity(len),
+            line: Vec::new(),
+        }
+    }
+
+    /// Returns a mutable reference to the reader, e.g. to append to it between refreshes.
+    pub fn get_mut(&mut self) -> &mut R {
+        self.raw.reader.get_mut()
+    }
+
+    /// Read the last lines of the reader again, returning them in forward order.
+    ///
+    /// The `String`s of the previous refresh are reused, so refreshing only allocates
+    /// when lines got longer or the window was not full yet.
+    pub fn refresh(&mut self) -> Result<&[String], RevLinesError> {
+        self.raw.reset();
+        let mut count = 0;
+
+        while count < self.len {
+            let Some(offset) = self.raw.read_line_at(&mut self.line)? else {
+                break;
+            };
+            let Ok(line) = std::str::from_utf8(&self.line) else {
+                let source = String::from_utf8(std::mem::take(&mut self.line)).unwrap_err();
+                return Err(RevLinesError::InvalidUtf8 { offset, source });
+            };
+
+            match self.lines.get_mut(count) {
+                Some(slot) => {
+                    slot.clear();
+                    slot.push_str(line);
+                }
+                None => self.lines.push(line.to_string()),
+            }
+            count += 1;
+        }
+
+        self.lines.truncate(count);
+        self.lines.reverse();
+        Ok(&self.lines)
+    }
+}
+
+/// Iterator returned by [`RevLines::from_string`]
+pub struct StrRevLines<'a> {
+    pub(crate) text: &'a str,
+    pub(crate) raw: RawRevLines<Cursor<&'a [u8]>>,
+}
+
+impl<'a> Iterator for StrRevLines<'a> {
+    type Item = &'a str;
+
+    fn next(&mut self) -> Option<&'a str> {
+        let (start, _) = self
+            .raw
+            .next_line_at()
+            .expect("reading from an in-memory cursor cannot fail")?;
+        let end = start as usize + self.raw.line_len;
+
+        Some(&self.text[start as usize..end])
+    }
+}
+
+/// Iterator returned by [`RevLines::with_exact_len`]
+///
+/// The length is only exact as long as the reader is not modified while iterating.
+pub struct ExactRevLines<R, D = Utf8Decoder> {
+    pub(crate) rev_lines: RevLines<R, D>,
+    pub(crate) remaining: usize,
+}
+
+impl<R: Read + Seek, D: LineDecoder> Iterator for ExactRevLines<R, D> {
+    type Item = Result<String, RevLinesError>;
+
+    fn next(&mut self) -> Option<Result<String, RevLinesError>> {
+        let line = self.rev_lines.next()?;
+        self.remaining = self.remaining.saturating_sub(1);
+        Some(line)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<R: Read + Seek, D: LineDecoder> ExactSizeIterator for ExactRevLines<R, D> {}
+
+/// Iterator returned by [`RevLines::lines_numbered`]
+pub struct NumberedRevLines<R, D = Utf8Decoder> {
+    pub(crate) rev_lines: RevLines<R, D>,
+    /// Line offsets from the end of the reader backwards, built on the first call to `next`
+    pub(crate) offsets: Option<Vec<u64>>,
+    pub(crate) failed: bool,
+}
+
+impl<R: Read + Seek, D: LineDecoder> Iterator for NumberedRevLines<R, D> {
+    type Item = Result<(String, u64), RevLinesError>;
+
+    fn next(&mut self) -> Option<Result<(String, u64), RevLinesError>> {
+        if self.failed {
+            return None;
+        }
+
+        if self.offsets.is_none() {
+            let raw = &mut self.rev_lines.raw;
+            // Index every line, not only those left within the budget of `max_lines`
+            raw.reset();
+            let offsets = raw.without_max_lines(RawRevLines::line_offsets);
+            raw.reset();
+
+            match offsets {
+                Ok(offsets) => self.offsets = Some(offsets),
+                Err(error) => {
+                    // Nothing can be numbered without the index, so stop after the error
+                    self.failed = true;
+                    return Some(Err(RevLinesError::Io(error)));
+                }
+            }
+        }
+
+        let line = match self.rev_lines.next()? {
+            Ok(line) => line,
+            Err(error) => return Some(Err(error)),
+        };
+
+        let offsets = self.offsets.as_deref().unwrap_or_default();
+        let offset = self.rev_lines.raw.last_offset;
+        // Offsets are sorted from last to first, so they are searched in reverse order
+        let index = offsets
+            .binary_search_by(|probe| offset.cmp(probe))
+            .unwrap_or_else(|index| index);
+
+        Some(Ok((line, (offsets.len() - index) as u64)))
+    }
+}
+
+/// Iterator returned by [`RevLines::tail_forward`]
+pub struct TailForward<R> {
+    pub(crate) rev_lines: Option<RevLines<R>>,
+    pub(crate) n: usize,
+    pub(crate) lines: Vec<Result<String, RevLinesError>>,
+}
+
+impl<R: Read + Seek> Iterator for TailForward<R> {
+    type Item = Result<String, RevLinesError>;
+
+    fn next(&mut self) -> Option<Result<String, RevLinesError>> {
+        if let Some(rev_lines) = self.rev_lines.take() {
+            // Lines are collected newest-first, so popping hands them out oldest-first
+            self.lines = rev_lines.take(self.n).collect();
+        }
+
+        self.lines.pop()
+    }
+}
@@ -0,0 +1,110 @@
+//! Decoders which turn the bytes of lines into `String`s for [`RevLines`].
+//!
+//! [`RevLines`]: crate::RevLines
+
+use std::cell::OnceCell;
+
+use crate::RevLinesError;
+
+/// Turns the bytes of a line into a `String`.
+///
+/// Lines are always split on bytes, so only encodings which keep line terminators as
+/// single bytes, like UTF-8, Latin-1 or Shift JIS, can be decoded this way.
+pub trait LineDecoder {
+    /// Decode the bytes of a single line, without its terminator
+    fn decode(&self, bytes: &[u8]) -> Result<String, RevLinesError>;
+
+    /// Same as `decode`, for when the bytes are owned and may be reused for the `String`
+    fn decode_owned(&self, bytes: Vec<u8>) -> Result<String, RevLinesError> {
+        self.decode(&bytes)
+    }
+}
+
+/// Decodes lines as UTF-8, failing with `RevLinesError::InvalidUtf8` on invalid input.
+/// This is the default decoder of `RevLines`.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Utf8Decoder;
+
+impl LineDecoder for Utf8Decoder {
+    fn decode(&self, bytes: &[u8]) -> Result<String, RevLinesError> {
+        self.decode_owned(bytes.to_vec())
+    }
+
+    fn decode_owned(&self, bytes: Vec<u8>) -> Result<String, RevLinesError> {
+        Ok(String::from_utf8(bytes)?)
+    }
+}
+
+/// Decodes lines as UTF-8, replacing invalid sequences with `U+FFFD`.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Utf8LossyDecoder;
+
+impl LineDecoder for Utf8LossyDecoder {
+    fn decode(&self, bytes: &[u8]) -> Result<String, RevLinesError> {
+        Ok(String::from_utf8_lossy(bytes).into_owned())
+    }
+
+    fn decode_owned(&self, bytes: Vec<u8>) -> Result<String, RevLinesError> {
+        match String::from_utf8(bytes) {
+            Ok(line) => Ok(line),
+            Err(error) => self.decode(error.as_bytes()),
+        }
+    }
+}
+
+/// Encodings an [`EncodingSniffer`] can pick for the lines of a reader
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Encoding {
+    /// Decode like [`Utf8Decoder`]. This is the default.
+    #[default]
+    Utf8,
+    /// Decode like [`Utf8LossyDecoder`].
+    Utf8Lossy,
+    /// Decode every byte as the code point of the same value, as in ISO 8859-1.
+    Latin1,
+}
+
+impl LineDecoder for Encoding {
+    fn decode(&self, bytes: &[u8]) -> Result<String, RevLinesError> {
+        match self {
+            Encoding::Utf8 => Utf8Decoder.decode(bytes),
+            Encoding::Utf8Lossy => Utf8LossyDecoder.decode(bytes),
+            Encoding::Latin1 => Ok(bytes.iter().map(|byte| char::from(*byte)).collect()),
+        }
+    }
+
+    fn decode_owned(&self, bytes: Vec<u8>) -> Result<String, RevLinesError> {
+        match self {
+            Encoding::Utf8 => Utf8Decoder.decode_owned(bytes),
+            Encoding::Utf8Lossy => Utf8LossyDecoder.decode_owned(bytes),
+            Encoding::Latin1 => self.decode(&bytes),
+        }
+    }
+}
+
+/// Decoder returned by [`RevLines::with_encoding_sniffer`], which picks the [`Encoding`]
+/// of all lines from the first line it decodes.
+///
+/// [`RevLines::with_encoding_sniffer`]: crate::RevLines::with_encoding_sniffer
+pub struct EncodingSniffer<F> {
+    pub(crate) sniffer: F,
+    pub(crate) encoding: OnceCell<Encoding>,
+}
+
+impl<F: Fn(&[u8]) -> Option<Encoding>> LineDecoder for EncodingSniffer<F> {
+    fn decode(&self, bytes: &[u8]) -> Result<String, RevLinesError> {
+        self.encoding(bytes).decode(bytes)
+    }
+
+    fn decode_owned(&self, bytes: Vec<u8>) -> Result<String, RevLinesError> {
+        self.encoding(&bytes).decode_owned(bytes)
+    }
+}
+
+impl<F: Fn(&[u8]) -> Option<Encoding>> EncodingSniffer<F> {
+    fn encoding(&self, first_line: &[u8]) -> Encoding {
+        *self
+            .encoding
+            .get_or_init(|| (self.sniffer)(first_line).unwrap_or_default())
+    }
+}
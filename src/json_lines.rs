@@ -0,0 +1,144 @@
+//! Parse the lines of JSON Lines logs in reverse.
+//!
+//! Enabled with the `serde_json` feature.
+
+use std::io::{Read, Seek};
+
+use serde::de::DeserializeOwned;
+
+use crate::{LineDecoder, RevLines, RevLinesError};
+
+/// Error of [`RevLines::json_lines`]
+#[derive(Debug)]
+pub enum JsonLinesError {
+    /// Reading or decoding a line failed.
+    Line(RevLinesError),
+    /// A line is not valid JSON for the type it is parsed as. `offset` is the absolute
+    /// position of the first byte of the line in the reader.
+    Json {
+        offset: u64,
+        source: serde_json::Error,
+    },
+}
+
+impl std::fmt::Display for JsonLinesError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            JsonLinesError::Line(error) => std::fmt::Display::fmt(error, f),
+            JsonLinesError::Json { offset, source } => {
+                write!(
+                    f,
+                    "invalid JSON in line starting at byte offset {offset}: {source}"
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for JsonLinesError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            JsonLinesError::Line(error) => error.source(),
+            JsonLinesError::Json { source, .. } => Some(source),
+        }
+    }
+}
+
+impl From<RevLinesError> for JsonLinesError {
+    fn from(error: RevLinesError) -> JsonLinesError {
+        JsonLinesError::Line(error)
+    }
+}
+
+impl<R: Read + Seek, D: LineDecoder> RevLines<R, D> {
+    /// Parse every line as a JSON value of type `T`, last line first, e.g. to read the
+    /// newest records of a JSON Lines log.
+    ///
+    /// Lines are parsed lazily, one per call to `next`. A line which fails to parse is
+    /// yielded as an error and iteration continues with the line in front of it.
+    ///
+    /// ```
+    /// use std::io::Cursor;
+    ///
+    /// use rev_lines::RevLines;
+    /// use serde::Deserialize;
+    ///
+    /// #[derive(Debug, Deserialize, PartialEq)]
+    /// struct Event {
+    ///     id: u32,
+    ///     kind: String,
+    /// }
+    ///
+    /// let log = Cursor::new(concat!(
+    ///     "{\"id\": 1, \"kind\": \"start\"}\n",
+    ///     "{\"id\": 2, \"kind\": \"tick\"}\n",
+    ///     "{\"id\": 3, \"kind\": \"stop\"}\n",
+    /// ));
+    ///
+    /// let events: Vec<Event> = RevLines::new(log)
+    ///     .json_lines()
+    ///     .take(2)
+    ///     .collect::<Result<_, _>>()
+    ///     .unwrap();
+    ///
+    /// assert_eq!(events[0], Event { id: 3, kind: "stop".to_string() });
+    /// assert_eq!(events[1], Event { id: 2, kind: "tick".to_string() });
+    /// ```
+    pub fn json_lines<T: DeserializeOwned>(
+        mut self,
+    ) -> impl Iterator<Item = Result<T, JsonLinesError>> {
+        std::iter::from_fn(move || {
+            let line = match self.next()? {
+                Ok(line) => line,
+                Err(error) => return Some(Err(JsonLinesError::Line(error))),
+            };
+            let offset = self.raw.last_offset;
+
+            Some(
+                serde_json::from_str(&line)
+                    .map_err(|source| JsonLinesError::Json { offset, source }),
+            )
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use serde::Deserialize;
+
+    use crate::json_lines::JsonLinesError;
+    use crate::{RevLines, RevLinesError};
+
+    type TestResult = Result<(), Box<dyn std::error::Error>>;
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Record {
+        n: u32,
+    }
+
+    #[test]
+    fn it_handles_json_lines() -> TestResult {
+        let log = b"{\"n\": 1}\n{\"n\": \xff}\n{\"n\": 3}\nnot json\n{\"n\": 5}\n".to_vec();
+        let mut records = RevLines::new(Cursor::new(log)).json_lines::<Record>();
+
+        assert_eq!(records.next().transpose()?, Some(Record { n: 5 }));
+        assert!(matches!(
+            records.next(),
+            Some(Err(JsonLinesError::Json { offset: 27, .. }))
+        ));
+        assert_eq!(records.next().transpose()?, Some(Record { n: 3 }));
+        assert!(matches!(
+            records.next(),
+            Some(Err(JsonLinesError::Line(RevLinesError::InvalidUtf8 {
+                offset: 9,
+                ..
+            })))
+        ));
+        assert_eq!(records.next().transpose()?, Some(Record { n: 1 }));
+        assert!(records.next().is_none());
+
+        Ok(())
+    }
+}
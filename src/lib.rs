@@ -6,31 +6,157 @@
 //! #### Example
 //!
 //! ```
-//! use std::fs::File;
-//!
 //! use rev_lines::RevLines;
 //!
-//! let file = File::open("README.md").unwrap();
-//! let rev_lines = RevLines::new(file);
+//! let rev_lines = RevLines::open("README.md").unwrap();
 //!
 //! for line in rev_lines {
 //!     println!("{:?}", line);
 //! }
 //! ```
 //!
+//! Since `&mut R` is a reader as well, a borrowed file can be read in reverse and
+//! used again once the iterator is dropped. The file cursor is left wherever the last
+//! buffered read stopped, so seek to a known position before reading from it again.
+//!
+//! ```
+//! use std::fs::File;
+//! use std::io::{Read, Seek, SeekFrom};
+//!
+//! use rev_lines::RevLines;
+//!
+//! let mut file = File::open("README.md").unwrap();
+//! let last_line = RevLines::new(&mut file).next();
+//! assert!(last_line.is_some());
+//!
+//! file.seek(SeekFrom::Start(0)).unwrap();
+//! let mut contents = String::new();
+//! file.read_to_string(&mut contents).unwrap();
+//! assert_eq!(contents.lines().last(), Some(last_line.unwrap().unwrap().as_str()));
+//! ```
+//!
 //! If a line with invalid UTF-8 is encountered, the iterator will return `None` next, and stop iterating.
 //!
 //! This method uses logic borrowed from [uutils/coreutils tail](https://github.com/uutils/coreutils/blob/f2166fed0ad055d363aedff6223701001af090d3/src/tail/tail.rs#L399-L402)
 
-use std::cmp::min;
-use std::io::{self, BufReader, Read, Seek, SeekFrom};
-
+use memchr::memmem;
+use std::cell::OnceCell;
+use std::cmp::{min, Ordering};
+use std::collections::{HashSet, TryReserveError, VecDeque};
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Cursor, Read, Seek, SeekFrom};
+use std::num::NonZeroUsize;
+use std::ops::Range;
+use std::path::Path;
+use std::sync::Arc;
+#[cfg(feature = "thiserror")]
 use thiserror::Error;
 
+mod adapters;
+mod decoders;
+#[cfg(feature = "serde_json")]
+pub mod json_lines;
+#[cfg(feature = "fs2")]
+mod shared_lock;
+#[cfg(feature = "stream")]
+pub mod stream;
+#[cfg(feature = "testing")]
+pub mod testing;
+#[cfg(feature = "timeout")]
+mod timeout;
+#[cfg(feature = "tokio1")]
+pub mod tokio1;
+
+pub use adapters::{
+    BofItem, ExactRevLines, MergeReversed, NumberedRevLines, PeekableRevLines, RevLinesChain,
+    StrRevLines, TailForward, TailWindow, TakeWhilePrefix, TruncatedLines,
+};
+pub use decoders::{Encoding, EncodingSniffer, LineDecoder, Utf8Decoder, Utf8LossyDecoder};
+#[cfg(feature = "timeout")]
+pub use timeout::TimeoutReader;
+
 static DEFAULT_SIZE: usize = 4096;
 
 static LF_BYTE: u8 = b'\n';
 static CR_BYTE: u8 = b'\r';
+static FF_BYTE: u8 = 0x0c;
+static GS_BYTE: u8 = 0x1d;
+static RS_BYTE: u8 = 0x1e;
+// U+2028 LINE SEPARATOR and U+2029 PARAGRAPH SEPARATOR are E2 80 A8 and E2 80 A9 in UTF-8
+static UNICODE_BREAK_PREFIX: [u8; 2] = [0xe2, 0x80];
+static LS_LAST_BYTE: u8 = 0xa8;
+static PS_LAST_BYTE: u8 = 0xa9;
+
+/// What to do with line terminators at the very end of the reader
+///
+/// [`TrailingNewlinePolicy::TrimOne`] splits lines like [`str::split_terminator`] and
+/// [`TrailingNewlinePolicy::Keep`] like [`str::split`], in reverse. Since lines are yielded
+/// last first, the extra empty segment of `split` is the *first* item, so `b"a\nb\n"`
+/// yields `""`, `"b"`, `"a"` with `Keep` instead of `"b"`, `"a"`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TrailingNewlinePolicy {
+    /// A single trailing terminator ends the last line and does not start an empty one,
+    /// so `b"a\n"` yields `"a"` and `b"a\n\n"` yields `""`, `"a"`. This is the default,
+    /// and the same as [`str::split_terminator`].
+    #[default]
+    TrimOne,
+    /// Every terminator starts a new line, so `b"a\n"` yields `""`, `"a"` and
+    /// `b"a\n\n"` yields `""`, `""`, `"a"`. This is the same as [`str::split`].
+    Keep,
+    /// Empty lines at the end of the reader are skipped, so `b"a\n"` and `b"a\n\n"`
+    /// both yield `"a"`.
+    TrimAll,
+}
+
+/// What to do with `\r` bytes in lines split on [`LineEnding::Lf`]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum CrHandling {
+    /// Strip the `\r` of a `\r\n` terminator and keep any other `\r`, so `b"a\rb\r\n"`
+    /// yields `"a\rb"`. This is the default.
+    #[default]
+    StripBeforeLf,
+    /// Strip every `\r`, so `b"a\rb\r\n"` yields `"ab"`. This applies to all line endings.
+    StripAll,
+    /// Keep every `\r` as part of the line, so `b"a\rb\r\n"` yields `"a\rb\r"`.
+    Keep,
+}
+
+/// The line terminators recognized when splitting lines
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum LineEnding {
+    /// Lines end in `\n`. A single `\r` right before the `\n` is stripped as well,
+    /// any other `\r` is kept as part of the line, see [`CrHandling`]. This is the default.
+    #[default]
+    Lf,
+    /// Lines end only in `\r\n`. A bare `\n` or `\r` is kept as part of the line.
+    CrLf,
+    /// Lines end only in `\r`, as in classic Mac OS files. A `\n` is kept as part of the line.
+    Cr,
+    /// Lines end in any of `\n`, `\r\n` or a bare `\r`.
+    Auto,
+    /// Lines end in any of `\n`, `\r\n`, a bare `\r`, or one of the control characters
+    /// form feed `\x0c`, group separator `\x1d` and record separator `\x1e`, which some
+    /// terminal logs and ASCII-armored data use as section breaks.
+    ControlChars,
+    /// Lines end in the given byte, such as `\0` for the output of `find -print0`.
+    /// A `\r` is always kept as part of the line.
+    Byte(u8),
+}
+
+impl LineEnding {
+    /// Whether `byte` can be the last byte of a line terminator
+    fn ends_terminator(self, byte: u8) -> bool {
+        match self {
+            LineEnding::Lf | LineEnding::CrLf => byte == LF_BYTE,
+            LineEnding::Cr => byte == CR_BYTE,
+            LineEnding::Auto => byte == LF_BYTE || byte == CR_BYTE,
+            LineEnding::ControlChars => {
+                [LF_BYTE, CR_BYTE, FF_BYTE, GS_BYTE, RS_BYTE].contains(&byte)
+            }
+            LineEnding::Byte(delimiter) => byte == delimiter,
+        }
+    }
+}
 
 /// `RevLines` struct
 pub struct RawRevLines<R> {
@@ -40,6 +166,50 @@ pub struct RawRevLines<R> {
     buffer_end: usize,
     read_len: usize,
     was_last_byte_line_feed: bool,
+    pending_terminator: usize,
+    line_ending: LineEnding,
+    unicode_line_breaks: bool,
+    trim_trailing_cr: bool,
+    require_trailing_newline: bool,
+    assume_trailing_newline: bool,
+    // Every read is one record of `buffer.len()` bytes, returned without looking for terminators
+    fixed_width: bool,
+    cr_handling: CrHandling,
+    trailing_newline_policy: TrailingNewlinePolicy,
+    trim_trailing_empty: usize,
+    keep_terminators: bool,
+    // Empty lines at the end of the reader still to be skipped
+    empty_lines_to_skip: usize,
+    skip_null_runs: bool,
+    normalize_endings: bool,
+    convert_lone_cr: bool,
+    max_refills: Option<usize>,
+    max_line_len: Option<usize>,
+    max_lines: Option<usize>,
+    lines_read: usize,
+    // Line given back with `rewind_one_line`, along with the offset of the line read last
+    pushed_back: Option<(u64, Vec<u8>)>,
+    join_continuations: bool,
+    // Line scanned to check whether it continues onto the line after it
+    lookahead: Option<ScannedLine>,
+    last_offset: u64,
+    // End of the line read last in the reader, before its terminator
+    last_end: u64,
+    truncate_len: Option<usize>,
+    line_truncated: bool,
+    // Length of the last line read, including any truncated bytes
+    line_len: usize,
+    // Terminator which followed the last line read in the reader
+    terminator: Terminator,
+    line_buf: Vec<u8>,
+    // Position reading backwards started from
+    reader_end: u64,
+    // Start of the next line read from the front with `next_back`
+    front_cursor: u64,
+    // Position to treat as the end of the reader instead of its actual end
+    end_position: Option<u64>,
+    read_alignment: u64,
+    progress: Option<Box<dyn FnMut(u64, u64) + Send>>,
 }
 
 impl<R: Seek + Read> RawRevLines<R> {
@@ -52,223 +222,5229 @@ impl<R: Seek + Read> RawRevLines<R> {
     /// Create a new `RawRevLines` struct from a Reader`.
     /// Internal buffering for iteration will use `cap` bytes at a time.
     pub fn with_capacity(cap: usize, reader: R) -> RawRevLines<R> {
+        RawRevLines::with_buffers(vec![0; cap], Vec::new(), reader)
+    }
+
+    /// Create a new `RawRevLines` struct which splits lines on `delim`, such as `\0` for the
+    /// output of `find -print0`. A `\r` is kept as part of the line unless `delim` is `\n`.
+    ///
+    /// This is the same as setting [`RawRevLines::line_ending`] to [`LineEnding::Byte`], or
+    /// to [`LineEnding::Lf`] for `\n`.
+    pub fn with_delimiter(delim: u8, reader: R) -> RawRevLines<R> {
+        RawRevLines::with_capacity_and_delimiter(DEFAULT_SIZE, delim, reader)
+    }
+
+    /// Same as [`RawRevLines::with_delimiter`], reading `cap` bytes at a time.
+    pub fn with_capacity_and_delimiter(cap: usize, delim: u8, reader: R) -> RawRevLines<R> {
+        let line_ending = match delim {
+            b'\n' => LineEnding::Lf,
+            delim => LineEnding::Byte(delim),
+        };
+
+        RawRevLines::with_capacity(cap, reader).line_ending(line_ending)
+    }
+
+    /// Create a new `RawRevLines` struct which treats the current position of the reader
+    /// as its end, so only the bytes in front of it are read.
+    pub fn from_current_position(mut reader: R) -> io::Result<RawRevLines<R>> {
+        let end = reader.stream_position()?;
+        let mut rev_lines = RawRevLines::new(reader);
+        rev_lines.end_position = Some(end);
+
+        Ok(rev_lines)
+    }
+
+    /// Create a new `RawRevLines` struct which yields records of `width` bytes instead of
+    /// lines, for fixed-width data files without delimiters.
+    ///
+    /// Records are counted from the start of the reader, so when its length is not a
+    /// multiple of `width` the shorter, partial record is the one at the end of the reader,
+    /// which is yielded first. Options which change how lines are split or trimmed, like
+    /// [`RawRevLines::line_ending`], have no effect. A `width` of 0 yields no records.
+    ///
+    /// ```
+    /// use std::io::Cursor;
+    ///
+    /// use rev_lines::RawRevLines;
+    ///
+    /// let records = RawRevLines::with_fixed_width(3, Cursor::new("AAABBBCC"))
+    ///     .collect::<Result<Vec<_>, _>>()
+    ///     .unwrap();
+    ///
+    /// assert_eq!(records, [&b"CC"[..], b"BBB", b"AAA"]);
+    /// ```
+    pub fn with_fixed_width(width: usize, reader: R) -> RawRevLines<R> {
+        let mut rev_lines = RawRevLines::with_capacity(width, reader).align_reads(width as u64);
+        rev_lines.fixed_width = true;
+        rev_lines
+    }
+
+    /// Create a new `RawRevLines` struct which treats `offset` as the end of the reader, so
+    /// only the bytes in front of it are read, e.g. to start from a spot found in an index.
+    ///
+    /// Nothing at or after `offset` is ever read from `reader`, which is read without any
+    /// buffering besides that of `RawRevLines` itself. A line which runs past `offset` is
+    /// cut off there, as whether it ends right at `offset` can't be told without reading on.
+    /// An `offset` past the end of the reader reads the whole reader.
+    ///
+    /// ```
+    /// use std::io::Cursor;
+    ///
+    /// use rev_lines::RawRevLines;
+    ///
+    /// let lines = RawRevLines::with_start_offset(7, Cursor::new("AB\nCD\nEF\n"))
+    ///     .collect::<Result<Vec<_>, _>>()
+    ///     .unwrap();
+    ///
+    /// assert_eq!(lines, [&b"E"[..], b"CD", b"AB"]);
+    /// ```
+    pub fn with_start_offset(offset: u64, reader: R) -> RawRevLines<R> {
+        // A BufReader without capacity passes every read straight on, without reading ahead
+        let mut rev_lines = RawRevLines::from_parts(
+            vec![0; DEFAULT_SIZE],
+            Vec::new(),
+            BufReader::with_capacity(0, reader),
+        );
+        rev_lines.end_position = Some(offset);
+        rev_lines
+    }
+
+    /// Same as [`RawRevLines::with_capacity`], with a capacity that can't be zero.
+    pub fn with_nonzero_capacity(cap: NonZeroUsize, reader: R) -> RawRevLines<R> {
+        RawRevLines::with_capacity(cap.get(), reader)
+    }
+
+    /// Same as [`RawRevLines::with_capacity`], but returns an error instead of aborting
+    /// when the buffer of `cap` bytes can't be allocated.
+    pub fn try_with_capacity(cap: usize, reader: R) -> Result<RawRevLines<R>, TryReserveError> {
+        let mut buffer = Vec::new();
+        buffer.try_reserve_exact(cap)?;
+
+        Ok(RawRevLines::with_buffers(buffer, Vec::new(), reader))
+    }
+
+    /// Create a new `RawRevLines` struct from a Reader, using caller-provided buffers.
+    ///
+    /// `read_buf` is used at its full allocated capacity as the buffer for reading from
+    /// the reader, so `Vec::with_capacity(cap)` behaves like `with_capacity(cap, reader)`.
+    /// `line_buf` backs [`RawRevLines::read_line`]; it is cleared between lines and only
+    /// grows when a line does not fit. Together they allow iterating without allocating.
+    pub fn with_buffers(read_buf: Vec<u8>, line_buf: Vec<u8>, reader: R) -> RawRevLines<R> {
+        RawRevLines::from_parts(read_buf, line_buf, BufReader::new(reader))
+    }
+
+    fn from_parts(
+        mut read_buf: Vec<u8>,
+        line_buf: Vec<u8>,
+        reader: BufReader<R>,
+    ) -> RawRevLines<R> {
+        read_buf.resize(read_buf.capacity(), 0);
+
         RawRevLines {
-            reader: BufReader::new(reader),
+            reader,
             reader_cursor: u64::MAX,
-            buffer: vec![0; cap],
+            buffer: read_buf,
             buffer_end: 0,
             read_len: 0,
             was_last_byte_line_feed: false,
+            pending_terminator: 0,
+            line_ending: LineEnding::Lf,
+            trim_trailing_cr: false,
+            require_trailing_newline: false,
+            assume_trailing_newline: false,
+            fixed_width: false,
+            cr_handling: CrHandling::StripBeforeLf,
+            trailing_newline_policy: TrailingNewlinePolicy::TrimOne,
+            unicode_line_breaks: false,
+            trim_trailing_empty: 0,
+            keep_terminators: false,
+            empty_lines_to_skip: 0,
+            skip_null_runs: false,
+            normalize_endings: false,
+            convert_lone_cr: false,
+            max_refills: None,
+            max_line_len: None,
+            max_lines: None,
+            lines_read: 0,
+            pushed_back: None,
+            join_continuations: false,
+            lookahead: None,
+            last_offset: 0,
+            last_end: 0,
+            truncate_len: None,
+            line_truncated: false,
+            line_len: 0,
+            terminator: Terminator::default(),
+            line_buf,
+            reader_end: 0,
+            front_cursor: 0,
+            end_position: None,
+            read_alignment: 1,
+            progress: None,
         }
     }
 
-    fn init_reader(&mut self) -> io::Result<()> {
-        // Move cursor to the end of the file and store the cursor position
-        self.reader_cursor = self.reader.seek(SeekFrom::End(0))?;
-        // Next read will be the full buffer size or the remaining bytes in the file
-        self.read_len = min(self.buffer.len(), self.reader_cursor as usize);
-        // Move cursor just before the next bytes to read
-        self.reader.seek_relative(-(self.read_len as i64))?;
-        // Update the cursor position
-        self.reader_cursor -= self.read_len as u64;
+    /// Returns the number of bytes read from the reader at a time.
+    pub fn capacity(&self) -> usize {
+        self.buffer.len()
+    }
 
-        self.read_to_buffer()?;
+    /// Set which line terminators split lines. Defaults to [`LineEnding::Lf`].
+    pub fn line_ending(mut self, line_ending: LineEnding) -> RawRevLines<R> {
+        self.line_ending = line_ending;
+        self
+    }
 
-        // Handle any trailing new line characters for the reader
-        // so the first next call does not return Some("")
-        if self.buffer_end > 0 {
-            if let Some(last_byte) = self.buffer.get(self.buffer_end - 1) {
-                if *last_byte == LF_BYTE {
-                    self.buffer_end -= 1;
-                    self.was_last_byte_line_feed = true;
-                }
-            }
+    /// Also split lines on the Unicode line separator `U+2028` and paragraph separator
+    /// `U+2029`, on top of the terminators of the line ending.
+    ///
+    /// Both are matched as their three byte UTF-8 encodings, so this only makes sense for
+    /// UTF-8 content. The separators end lines when reading backwards only, helpers which
+    /// read forward, like [`RawRevLines::as_forward_reader`], do not split on them.
+    pub fn unicode_line_breaks(mut self, enable: bool) -> RawRevLines<R> {
+        self.unicode_line_breaks = enable;
+        self
+    }
+
+    /// Change the delimiter between lines, taking effect from the next line read.
+    ///
+    /// The terminator of the line read last is still skipped as it was found, so a file
+    /// can switch delimiters between sections. `\n` selects [`LineEnding::Lf`], any other
+    /// byte [`LineEnding::Byte`], which leaves a `\r` in front of it untouched.
+    pub fn set_delimiter(&mut self, delimiter: u8) {
+        if delimiter == LF_BYTE {
+            self.line_ending = LineEnding::Lf;
+        } else {
+            self.line_ending = LineEnding::Byte(delimiter);
+            self.was_last_byte_line_feed = false;
         }
+    }
 
-        Ok(())
+    /// Treat a bare `\r` as the very last byte of the reader as the terminator of the last line.
+    ///
+    /// Off by default, so with [`LineEnding::Lf`] or [`LineEnding::CrLf`] `b"abc\r"` yields
+    /// `"abc\r"`, and `"abc"` with this enabled. With [`LineEnding::Cr`] or
+    /// [`LineEnding::Auto`] a trailing `\r` already ends the line, so both settings yield `"abc"`.
+    pub fn trim_trailing_cr(mut self, trim: bool) -> RawRevLines<R> {
+        self.trim_trailing_cr = trim;
+        self
     }
 
-    fn read_to_buffer(&mut self) -> io::Result<()> {
-        // Read the next bytes into the buffer, self.read_len was already prepared for that
-        self.reader.read_exact(&mut self.buffer[0..self.read_len])?;
-        // Specify which part of the buffer is valid
-        self.buffer_end = self.read_len;
-
-        // Determine what the next read length will be
-        let next_read_len = min(self.buffer.len(), self.reader_cursor as usize);
-        // Move the cursor just in front of the next read
-        self.reader
-            .seek_relative(-((self.read_len + next_read_len) as i64))?;
-        // Update cursor position
-        self.reader_cursor -= next_read_len as u64;
+    /// Join lines ending in a backslash with the line after them, as shell scripts and
+    /// config files continue long lines, so `b"a\\\nb\n"` yields `"ab"`.
+    ///
+    /// The backslash and the terminator after it are removed. Reading backwards, a line is
+    /// only returned once the line in front of it was read and found not to end in a
+    /// backslash. Off by default.
+    pub fn join_continuations(mut self, join: bool) -> RawRevLines<R> {
+        self.join_continuations = join;
+        self
+    }
 
-        // Store the next read length, it'll be used in the next call
-        self.read_len = next_read_len;
+    /// Fail with an `io::Error` of kind `InvalidData` instead of yielding any line when the
+    /// reader does not end in a line terminator, as POSIX requires of text files.
+    ///
+    /// An empty reader is accepted. After the error the iterator yields no further lines.
+    pub fn require_trailing_newline(mut self, require: bool) -> RawRevLines<R> {
+        self.require_trailing_newline = require;
+        self
+    }
 
-        Ok(())
+    /// Treat a reader which does not end in a line terminator as if it did, so
+    /// `b"a\nb"` yields the same lines as `b"a\nb\n"` under every
+    /// [`TrailingNewlinePolicy`].
+    ///
+    /// With [`TrailingNewlinePolicy::Keep`] this yields an empty line first, at the end of
+    /// the reader. The other policies already yield the same lines for both. A reader
+    /// assumed to end in a terminator also passes [`RawRevLines::require_trailing_newline`].
+    pub fn assume_trailing_newline(mut self, assume: bool) -> RawRevLines<R> {
+        self.assume_trailing_newline = assume;
+        self
     }
 
-    fn next_line(&mut self) -> io::Result<Option<Vec<u8>>> {
-        // Reader cursor will only ever be u64::MAX if the reader has not been initialized
-        // If by some chance the reader is initialized with a file of length u64::MAX this will still work,
-        // as some read length value is subtracted from the cursor position right away
+    /// Set what happens to `\r` bytes in lines. Defaults to [`CrHandling::StripBeforeLf`].
+    pub fn cr_handling(mut self, handling: CrHandling) -> RawRevLines<R> {
+        self.cr_handling = handling;
+        self
+    }
+
+    /// Set how line terminators at the end of the reader are handled.
+    /// Defaults to [`TrailingNewlinePolicy::TrimOne`].
+    pub fn trailing_newline_policy(mut self, policy: TrailingNewlinePolicy) -> RawRevLines<R> {
+        self.trailing_newline_policy = policy;
+        self
+    }
+
+    /// Keep the terminator bytes at the end of every line, such as `b"\r\n"`, so
+    /// `b"A\nB\r\n"` yields `b"B\r\n"` and `b"A\n"`, e.g. to write the lines back out as
+    /// they were. A last line without a terminator is yielded as it is.
+    ///
+    /// Like with [`RawRevLines::lines_with_terminators`], concatenating the lines in reverse
+    /// order rebuilds the reader byte for byte, as long as no option which alters or skips
+    /// lines is set.
+    pub fn keep_terminators(mut self, keep: bool) -> RawRevLines<R> {
+        self.keep_terminators = keep;
+        self
+    }
+
+    /// Skip up to `n` empty lines at the end of the reader, which are the first lines read,
+    /// so a few blank lines after the last line of content are not yielded. Empty lines in
+    /// front of the first non-empty line read are kept.
+    ///
+    /// This applies on top of the trailing newline policy, so with the default policy the
+    /// terminator of the last line does not count as an empty line.
+    /// [`TrailingNewlinePolicy::TrimAll`] skips every empty line at the end instead.
+    pub fn trim_trailing_empty(mut self, n: usize) -> RawRevLines<R> {
+        self.trim_trailing_empty = n;
+        self
+    }
+
+    /// Fail with an `io::Error` of kind `InvalidData` when reading a single line takes more
+    /// than `n` refills of the buffer, to bound the work spent on input without line breaks.
+    ///
+    /// Unlike [`RawRevLines::truncate_long_lines`] this limits reads rather than memory. After
+    /// the error the scan stays in the middle of the line, so the next read continues it.
+    pub fn max_refills_per_line(mut self, n: usize) -> RawRevLines<R> {
+        self.max_refills = Some(n);
+        self
+    }
+
+    /// Fail with an `io::Error` of kind `InvalidData` when a single line is longer than
+    /// `limit` bytes, to bound the memory spent on input without line breaks.
+    ///
+    /// The bytes collected for the line are discarded. If the line was not read up to its
+    /// start yet, the scan stays in the middle of it, so the next read continues it.
+    pub fn max_line_len(mut self, limit: usize) -> RawRevLines<R> {
+        self.max_line_len = Some(limit);
+        self
+    }
+
+    /// Strip every `\r` at the end of lines, for logs gathered from mixed sources, so
+    /// `b"a\r\r\n"` yields `"a"`.
+    ///
+    /// This is on top of the line ending, which only strips the terminator. A `\r` within
+    /// a line is kept, unless [`RawRevLines::convert_lone_cr`] is set as well.
+    pub fn normalize_endings(mut self, normalize: bool) -> RawRevLines<R> {
+        self.normalize_endings = normalize;
+        self
+    }
+
+    /// Along with [`RawRevLines::normalize_endings`], replace every `\r` left within a line
+    /// with `\n`, so `b"a\rb\r\n"` yields `"a\nb"`. Has no effect on its own.
+    pub fn convert_lone_cr(mut self, convert: bool) -> RawRevLines<R> {
+        self.convert_lone_cr = convert;
+        self
+    }
+
+    /// Start every read from the reader at a multiple of `alignment` bytes, for block
+    /// devices and filesystems which are faster with aligned reads.
+    ///
+    /// Reads are shortened rather than extended to reach an aligned start, so they can be
+    /// up to `alignment - 1` bytes smaller than the capacity. Use a capacity which is a
+    /// multiple of `alignment` to keep every read but the one at the end of the reader
+    /// full-sized. A capacity smaller than `alignment` leaves reads unaligned.
+    pub fn align_reads(mut self, alignment: u64) -> RawRevLines<R> {
+        self.read_alignment = alignment;
+        self
+    }
+
+    /// Call `cb` with `(bytes_processed, total_bytes)` every time the buffer was refilled
+    /// from the reader, e.g. to update a progress bar.
+    ///
+    /// The callback fires once per `capacity` bytes read, so how often it is called depends
+    /// on the capacity rather than on the number of lines. `total_bytes` is the length of
+    /// the reader, or the position iteration continued from after
+    /// [`RawRevLines::seek_to_position`].
+    pub fn progress<F: FnMut(u64, u64) + Send + 'static>(mut self, cb: F) -> RawRevLines<R> {
+        self.progress = Some(Box::new(cb));
+        self
+    }
+
+    /// Stop after `n` lines, without reading any further from the reader.
+    ///
+    /// Unlike `.take(n)`, the budget is known to the iterator, so it is reflected in
+    /// `size_hint` and counts like [`RawRevLines::count_lines`].
+    pub fn max_lines(mut self, n: usize) -> RawRevLines<R> {
+        self.max_lines = Some(n);
+        self
+    }
+
+    /// Collapse every run of `\0` bytes within a line into a single `\0`.
+    ///
+    /// Reading through a hole in a sparse file, such as a core dump or a preallocated log,
+    /// yields zero bytes. They are ordinary content and kept as is by default, but a large
+    /// hole would otherwise come back as one line of millions of zeros. Offsets and ranges
+    /// reported for a line still refer to its bytes in the reader.
+    pub fn skip_null_runs(mut self, skip: bool) -> RawRevLines<R> {
+        self.skip_null_runs = skip;
+        self
+    }
+
+    /// Return lines longer than `max` bytes truncated to their last `max` bytes instead of
+    /// collecting them in full. Lines are read backward, so the end of a long line is kept.
+    ///
+    /// The returned iterator pairs each line with a flag telling whether it was truncated.
+    pub fn truncate_long_lines(mut self, max: usize) -> TruncatedLines<R> {
+        self.truncate_len = Some(max);
+
+        TruncatedLines(self)
+    }
+
+    /// Eagerly perform the initial seek to the end of the reader and fill the
+    /// internal buffer with the last `cap` bytes.
+    ///
+    /// This work normally happens lazily on the first call to `next`. Calling this
+    /// method up front moves that cost to construction time so the first `next`
+    /// is as cheap as the ones that follow. Calling it more than once is a no-op.
+    pub fn fill_to_capacity(&mut self) -> io::Result<()> {
         if self.reader_cursor == u64::MAX {
             self.init_reader()?;
         }
 
-        // For most sane scenarios, where size of the buffer is greater than the length of the line,
-        // the result will only contain one and at most two elements, making the flattening trivial.
-        // At the same time, instead of pushing one element at a time, it allows us to copy a subslice of the buffer,
-        // which is very performant on modern architectures.
-        let mut result: Vec<Vec<u8>> = Vec::new();
+        Ok(())
+    }
 
-        'outer: loop {
-            // Current buffer was read to completion, read new contents
+    /// Yield lines only while `pred` returns `true` for the raw line bytes, stopping
+    /// for good at the first line it rejects.
+    ///
+    /// Since lines come back newest-first, this is a cheap way to stop reading a
+    /// time-ordered log once lines older than some cutoff are reached, without
+    /// scanning the rest of the file.
+    pub fn take_while_prefix<F: Fn(&[u8]) -> bool>(self, pred: F) -> TakeWhilePrefix<R, F> {
+        TakeWhilePrefix {
+            rev_lines: self,
+            pred,
+            done: false,
+        }
+    }
+
+    /// Find the offset of the nearest byte in front of the current position for which
+    /// `pred` returns `true`, without splitting lines, e.g. to find the last `=` sign.
+    ///
+    /// Every byte not yet returned as part of a line is checked, including line terminators.
+    /// The matched byte and everything after it are consumed, so the next call finds the
+    /// match in front of it and the next line read ends right before it. When nothing
+    /// matches the whole reader is consumed.
+    ///
+    /// ```
+    /// use std::io::Cursor;
+    ///
+    /// use rev_lines::RawRevLines;
+    ///
+    /// let mut rev_lines = RawRevLines::new(Cursor::new("a=1\nb=2\n"));
+    ///
+    /// assert_eq!(rev_lines.scan_backwards_for(|byte| byte == b'=').unwrap(), Some(5));
+    /// assert_eq!(rev_lines.next().unwrap().unwrap(), b"b");
+    /// ```
+    pub fn scan_backwards_for<F: Fn(u8) -> bool>(&mut self, pred: F) -> io::Result<Option<u64>> {
+        self.fill_to_capacity()?;
+
+        // The line scanned ahead was not returned yet, so continue right after it
+        if self.lookahead.is_some() {
+            let position = RawRevLines::position(self);
+            self.seek_to_position(position)?;
+        }
+        // Lines given back are after the bytes being scanned
+        self.pushed_back = None;
+        self.pending_terminator = 0;
+        self.was_last_byte_line_feed = false;
+
+        loop {
             if self.buffer_end == 0 {
-                // Read the of minimum between the desired
-                // buffer size or remaining length of the reader
                 self.read_to_buffer()?;
+
+                if self.buffer_end == 0 {
+                    return Ok(None);
+                }
             }
 
-            // If buffer_end is still 0, it means the reader is empty
+            match self.buffer[..self.buffer_end]
+                .iter()
+                .rposition(|byte| pred(*byte))
+            {
+                Some(index) => {
+                    self.buffer_end = index;
+                    return Ok(Some(self.scan_position()));
+                }
+                None => self.buffer_end = 0,
+            }
+        }
+    }
+
+    /// Read the record in front of the current position and append it to `buf`, the
+    /// reverse of [`BufRead::read_until`]. Returns the number of bytes appended, 0 once
+    /// the start of the reader is reached.
+    ///
+    /// Like with `read_until`, records end in `byte` and include it, so the delimiter
+    /// appended is the one at the end of the record rather than the one in front of it.
+    /// Only the first record of the reader can't start after a delimiter, and only the last
+    /// one can lack one. Bytes are appended in their forward order and as they are,
+    /// without any special handling of `\r`.
+    ///
+    /// ```
+    /// use std::io::Cursor;
+    ///
+    /// use rev_lines::RawRevLines;
+    ///
+    /// let mut rev_lines = RawRevLines::new(Cursor::new("a;bb;c"));
+    /// let mut buf = Vec::new();
+    ///
+    /// assert_eq!(rev_lines.read_until_rev(b';', &mut buf).unwrap(), 1);
+    /// assert_eq!(rev_lines.read_until_rev(b';', &mut buf).unwrap(), 3);
+    /// assert_eq!(buf, b"cbb;");
+    /// ```
+    pub fn read_until_rev(&mut self, byte: u8, buf: &mut Vec<u8>) -> io::Result<usize> {
+        self.fill_to_capacity()?;
+
+        // The line scanned ahead was not returned yet, so continue right after it
+        if self.lookahead.is_some() {
+            let position = RawRevLines::position(self);
+            self.seek_to_position(position)?;
+        }
+        // Lines given back are after the bytes being read
+        self.pushed_back = None;
+        self.pending_terminator = 0;
+        self.was_last_byte_line_feed = false;
+
+        let mut record = Vec::new();
+        let mut chunks = 0;
+
+        loop {
             if self.buffer_end == 0 {
-                if result.is_empty() {
-                    return Ok(None);
-                } else {
+                self.read_to_buffer()?;
+
+                if self.buffer_end == 0 {
                     break;
                 }
             }
 
-            let mut buffer_length = self.buffer_end;
+            // The last byte of the record may be its own delimiter, so it is not searched
+            let search_end = match chunks {
+                0 => self.buffer_end - 1,
+                _ => self.buffer_end,
+            };
 
-            for ch in self.buffer[..self.buffer_end].iter().rev() {
-                self.buffer_end -= 1;
-                // Found a new line character to break on
-                if *ch == LF_BYTE {
-                    result.push(self.buffer[self.buffer_end + 1..buffer_length].to_vec());
-                    self.was_last_byte_line_feed = true;
-                    break 'outer;
+            match self.buffer[..search_end].iter().rposition(|b| *b == byte) {
+                Some(index) => {
+                    push_chunk(
+                        &mut record,
+                        &mut chunks,
+                        &self.buffer[index + 1..self.buffer_end],
+                        None,
+                    );
+                    // Leave the delimiter in front as the end of the record before this one.
+                    // When it also ends a line, the next line read skips it as usual
+                    self.buffer_end = index + 1;
+                    self.pending_terminator = self.terminator_len_at(index)?;
+                    break;
                 }
-                // If previous byte was line feed, skip carriage return
-                if *ch == CR_BYTE && self.was_last_byte_line_feed {
-                    buffer_length -= 1;
+                None => {
+                    push_chunk(
+                        &mut record,
+                        &mut chunks,
+                        &self.buffer[..self.buffer_end],
+                        None,
+                    );
+                    self.buffer_end = 0;
                 }
-                self.was_last_byte_line_feed = false;
             }
+        }
 
-            result.push(self.buffer[..buffer_length].to_vec());
+        if chunks > 1 {
+            record.reverse();
         }
+        buf.extend_from_slice(&record);
 
-        Ok(Some(result.into_iter().rev().flatten().collect()))
+        Ok(record.len())
     }
-}
 
-impl<R: Read + Seek> Iterator for RawRevLines<R> {
-    type Item = io::Result<Vec<u8>>;
+    /// Count the lines remaining in the reader, consuming them.
+    ///
+    /// Lines are scanned but not collected, so this is cheaper than counting the
+    /// items of the iterator.
+    pub fn count_lines(&mut self) -> io::Result<usize> {
+        // Keeping zero bytes of every line avoids copying them out of the buffer
+        let truncate_len = self.truncate_len.replace(0);
+        let mut count = 0;
 
-    fn next(&mut self) -> Option<io::Result<Vec<u8>>> {
-        self.next_line().transpose()
+        let result = loop {
+            match self.next_line() {
+                Ok(Some(_)) => count += 1,
+                Ok(None) => break Ok(count),
+                Err(error) => break Err(error),
+            }
+        };
+
+        self.truncate_len = truncate_len;
+        result
     }
-}
 
-#[derive(Debug, Error)]
-pub enum RevLinesError {
-    #[error(transparent)]
-    Io(#[from] std::io::Error),
-    #[error(transparent)]
-    InvalidUtf8(#[from] std::string::FromUtf8Error),
-}
+    /// Run `f` without the limit set with [`RawRevLines::max_lines`], for prepasses
+    /// which need every line of the reader
+    fn without_max_lines<T>(&mut self, f: impl FnOnce(&mut Self) -> T) -> T {
+        let max_lines = self.max_lines.take();
+        let result = f(self);
+        self.max_lines = max_lines;
+        result
+    }
 
-pub struct RevLines<R>(RawRevLines<R>);
+    /// Collect the offsets of the lines remaining in the reader, last line first,
+    /// consuming them. Like [`RawRevLines::count_lines`] no line is copied.
+    fn line_offsets(&mut self) -> io::Result<Vec<u64>> {
+        let truncate_len = self.truncate_len.replace(0);
+        let mut offsets = Vec::new();
+        let mut line = Vec::new();
 
-impl<R: Read + Seek> RevLines<R> {
-    /// Create a new `RawRevLines` struct from a Reader.
-    /// Internal buffering for iteration will default to 4096 bytes at a time.
-    pub fn new(reader: R) -> RevLines<R> {
-        RevLines(RawRevLines::new(reader))
+        let result = loop {
+            match self.read_line_at(&mut line) {
+                Ok(Some(offset)) => offsets.push(offset),
+                Ok(None) => break Ok(offsets),
+                Err(error) => break Err(error),
+            }
+        };
+
+        self.truncate_len = truncate_len;
+        result
     }
 
-    /// Create a new `RawRevLines` struct from a Reader`.
-    /// Internal buffering for iteration will use `cap` bytes at a time.
-    pub fn with_capacity(cap: usize, reader: R) -> RevLines<R> {
-        RevLines(RawRevLines::with_capacity(cap, reader))
+    /// Collect the count and length distribution of the lines remaining in the reader,
+    /// consuming them.
+    ///
+    /// Like [`RawRevLines::count_lines`], lines are scanned without being copied out of
+    /// the buffer. Lengths are in bytes, without line terminators.
+    pub fn stats(&mut self) -> io::Result<LineStats> {
+        let truncate_len = self.truncate_len.replace(0);
+        let mut stats = LineStats::default();
+        let mut line = Vec::new();
+
+        let result = loop {
+            match self.read_line_at(&mut line) {
+                Ok(Some(_)) => stats.push(self.line_len),
+                Ok(None) => break Ok(stats),
+                Err(error) => break Err(error),
+            }
+        };
+
+        self.truncate_len = truncate_len;
+        result
+    }
+
+    /// Count the lines in the last `window_bytes` bytes of the reader and the bytes they
+    /// take up, terminators included, in a single pass over the end of the reader.
+    ///
+    /// Only lines starting inside the window are counted. When the window starts in the
+    /// middle of a line it snaps forward to the next line boundary, so neither that partial
+    /// line nor its bytes are counted. Like [`RawRevLines::count_lines`] no line is copied.
+    ///
+    /// Lines are counted regardless of [`RawRevLines::max_lines`]. Afterwards
+    /// iteration continues from where it was.
+    pub fn reverse_lines_count_bytes(&mut self, window_bytes: u64) -> io::Result<(usize, u64)> {
+        let position = RawRevLines::position(self);
+        let (lines_read, front_cursor) = (self.lines_read, self.front_cursor);
+        let pushed_back = self.pushed_back.take();
+
+        self.reset();
+        let result = self.without_max_lines(|rev_lines| rev_lines.count_window(window_bytes));
+
+        self.seek_to_position(position)?;
+        self.lines_read = lines_read;
+        self.front_cursor = front_cursor;
+        self.pushed_back = pushed_back;
+        result
+    }
+
+    /// Count the lines starting in the last `window_bytes` bytes and the bytes they take up
+    fn count_window(&mut self, window_bytes: u64) -> io::Result<(usize, u64)> {
+        let end = self.reader_len()?;
+        let window_start = end.saturating_sub(window_bytes);
+
+        let truncate_len = self.truncate_len.replace(0);
+        let mut line = Vec::new();
+        let mut count = 0;
+        let mut first_offset = end;
+
+        let result = loop {
+            match self.read_line_at(&mut line) {
+                Ok(Some(offset)) if offset >= window_start => {
+                    count += 1;
+                    first_offset = offset;
+                }
+                Ok(_) => break Ok((count, end - first_offset)),
+                Err(error) => break Err(error),
+            }
+        };
+
+        self.truncate_len = truncate_len;
+        result
+    }
+
+    /// Estimate the total number of lines in the reader, e.g. to preallocate before
+    /// collecting them.
+    ///
+    /// The estimate is approximate: it counts the line terminators in the last few buffers
+    /// of the reader and scales that up to the length of the whole reader, so it is only
+    /// close when lines have a similar length throughout. Iteration is not affected.
+    pub fn expected_lines_hint(&mut self) -> io::Result<usize> {
+        let end = self.reader_len()?;
+        let sample_len = min(end, 4 * self.buffer.len().max(1) as u64);
+        let mut sample = vec![0; sample_len as usize];
+
+        self.reader.seek(SeekFrom::Start(end - sample_len))?;
+        let result = self.reader.read_exact(&mut sample);
+        self.restore_reader_position()?;
+        result?;
+
+        let terminators = sample
+            .iter()
+            .filter(|byte| self.line_ending.ends_terminator(**byte))
+            .count() as u64;
+        let hint = match terminators {
+            0 => u64::from(end > 0),
+            _ => (terminators * end).div_ceil(sample_len),
+        };
+
+        Ok(hint as usize)
+    }
+
+    /// Fold `f` over the raw bytes of the next `n` lines, or fewer if the reader runs out,
+    /// e.g. to hash the tail of a log without collecting it first.
+    ///
+    /// Lines are handed to `f` without their terminators, from a buffer reused between lines.
+    pub fn fold_bytes<T, F>(&mut self, init: T, n: usize, mut f: F) -> io::Result<T>
+    where
+        F: FnMut(T, &[u8]) -> T,
+    {
+        let mut acc = init;
+
+        for _ in 0..n {
+            match self.read_line()? {
+                Some(line) => acc = f(acc, line),
+                None => break,
+            }
+        }
+
+        Ok(acc)
+    }
+
+    /// Give back the line read last, so the next read returns it again.
+    ///
+    /// There is room for a single line, which replaces any line given back before. The
+    /// line is not checked against the reader and keeps the offset of the line read last.
+    pub fn rewind_one_line(&mut self, line: Vec<u8>) {
+        if self.pushed_back.is_none() {
+            self.lines_read = self.lines_read.saturating_sub(1);
+        }
+        self.pushed_back = Some((self.last_offset, line));
+    }
+
+    /// Pair each line with the exact bytes of the terminator which followed it in the reader,
+    /// such as `b"\r\n"`, or `b""` for a last line without one.
+    ///
+    /// Concatenating the pairs in reverse order rebuilds the reader byte for byte, as long
+    /// as no option which alters or skips lines, like [`TrailingNewlinePolicy::TrimAll`], is set.
+    pub fn lines_with_terminators(
+        mut self,
+    ) -> impl Iterator<Item = io::Result<(Vec<u8>, Vec<u8>)>> {
+        std::iter::from_fn(move || match self.next_line() {
+            Ok(Some(line)) => Some(Ok((line, self.terminator.as_bytes().to_vec()))),
+            Ok(None) => None,
+            Err(error) => Some(Err(error)),
+        })
+    }
+
+    /// Pair each line with the absolute offset of its first byte in the reader, for
+    /// building an index of where lines start.
+    ///
+    /// ```
+    /// use std::io::Cursor;
+    ///
+    /// use rev_lines::RawRevLines;
+    ///
+    /// let mut lines = RawRevLines::new(Cursor::new("first\r\nsecond\n")).lines_with_offsets();
+    ///
+    /// assert_eq!(lines.next().unwrap().unwrap(), (7, b"second".to_vec()));
+    /// assert_eq!(lines.next().unwrap().unwrap(), (0, b"first".to_vec()));
+    /// ```
+    pub fn lines_with_offsets(mut self) -> impl Iterator<Item = io::Result<(u64, Vec<u8>)>> {
+        std::iter::from_fn(move || self.next_line_at().transpose())
+    }
+
+    /// Read all remaining lines, leaving the iterator exhausted.
+    pub fn drain_to_end(&mut self) -> io::Result<Vec<Vec<u8>>> {
+        let mut lines = Vec::new();
+
+        while let Some(line) = self.next_line()? {
+            lines.push(line);
+        }
+
+        Ok(lines)
+    }
+
+    /// Returns a bookmark of how far iteration got, to continue from there later with
+    /// [`RawRevLines::seek_to_position`].
+    ///
+    /// The position is only meaningful for the same reader with the same content. A line
+    /// given back with [`RawRevLines::rewind_one_line`] is not part of it.
+    ///
+    /// ```
+    /// use std::io::Cursor;
+    ///
+    /// use rev_lines::RawRevLines;
+    ///
+    /// let mut rev_lines = RawRevLines::new(Cursor::new("A\nB\nC\n"));
+    /// rev_lines.next();
+    /// let position = rev_lines.position();
+    ///
+    /// assert_eq!(rev_lines.next().unwrap().unwrap(), b"B");
+    /// rev_lines.seek_to_position(position).unwrap();
+    /// assert_eq!(rev_lines.next().unwrap().unwrap(), b"B");
+    /// ```
+    pub fn position(&self) -> Position {
+        match (self.reader_cursor, &self.lookahead) {
+            (u64::MAX, _) => Position(u64::MAX),
+            // The line scanned ahead was not returned yet, so the position is after it
+            (_, Some(scanned)) => Position(scanned.end()),
+            _ => Position(self.scan_position()),
+        }
+    }
+
+    /// Continue iteration from a position returned by [`RawRevLines::position`].
+    ///
+    /// The next line is the one that followed when the position was taken. The number
+    /// of lines counted towards [`RawRevLines::max_lines`] is left as it is.
+    pub fn seek_to_position(&mut self, position: Position) -> io::Result<()> {
+        let lines_read = self.lines_read;
+        self.reset();
+        self.lines_read = lines_read;
+
+        // The reader was not initialized when the position was taken
+        if position.0 == u64::MAX {
+            return Ok(());
+        }
+
+        self.reader.seek(SeekFrom::Start(position.0))?;
+        self.init_reader_at(position.0, false)
+    }
+
+    /// Start iteration over from the end of the reader, reusing the buffer.
+    ///
+    /// The end is looked up again on the next read, so lines appended in the meantime are
+    /// read as well. The number of lines counted towards [`RawRevLines::max_lines`]
+    /// starts from zero again.
+    pub fn rewind(&mut self) -> io::Result<()> {
+        self.reset();
+        Ok(())
+    }
+
+    /// Unwraps this `RawRevLines`, returning the underlying reader, like
+    /// [`BufReader::into_inner`].
+    ///
+    /// The reader is left wherever the last read or seek of iteration put it, which is not
+    /// where the next line would start, so seek before reading from it. See
+    /// [`RawRevLines::as_forward_reader`] for a reader positioned at the last line read.
+    pub fn into_inner(self) -> R {
+        self.reader.into_inner()
+    }
+
+    /// Returns the reader, positioned to read forward from the start of the last line that
+    /// was read, along with that offset. Reading forward yields the lines read so far in
+    /// their original order, e.g. to stream on from a spot found by reading backward.
+    ///
+    /// Before any line was read the reader is positioned at its end.
+    ///
+    /// ```
+    /// use std::io::{Cursor, Read};
+    ///
+    /// use rev_lines::RawRevLines;
+    ///
+    /// let mut rev_lines = RawRevLines::new(Cursor::new("a\nb\nc\n"));
+    /// rev_lines.next();
+    /// rev_lines.next();
+    ///
+    /// let (mut reader, offset) = rev_lines.as_forward_reader().unwrap();
+    /// let mut rest = String::new();
+    /// reader.read_to_string(&mut rest).unwrap();
+    ///
+    /// assert_eq!((offset, rest.as_str()), (2, "b\nc\n"));
+    /// ```
+    pub fn as_forward_reader(mut self) -> io::Result<(R, u64)> {
+        let offset = match self.position().0 {
+            u64::MAX => self.reader_len()?,
+            position => position,
+        };
+
+        let mut reader = self.reader.into_inner();
+        reader.seek(SeekFrom::Start(offset))?;
+
+        Ok((reader, offset))
+    }
+
+    /// Position reading backwards starts from, the end of the reader unless set otherwise
+    fn reader_len(&mut self) -> io::Result<u64> {
+        let len = self.reader.seek(SeekFrom::End(0))?;
+
+        Ok(self.end_position.map_or(len, |end| min(end, len)))
+    }
+
+    /// Put the reader back where the next buffer read expects it, after reading elsewhere
+    fn restore_reader_position(&mut self) -> io::Result<()> {
+        if self.reader_cursor != u64::MAX {
+            self.reader.seek(SeekFrom::Start(self.reader_cursor))?;
+        }
+
+        Ok(())
+    }
+
+    /// Read the line starting at `start` forward into `line`, without reading past `end`.
+    /// Returns the position after its terminator, where the next line starts
+    fn read_line_forward(&mut self, start: u64, end: u64, line: &mut Vec<u8>) -> io::Result<u64> {
+        let (line_ending, cr_handling) = (self.line_ending, self.cr_handling);
+        self.reader.seek(SeekFrom::Start(start))?;
+
+        // A reader without a buffer of its own can't be peeked at, so lend it one. Seeking
+        // emptied the buffer of `self.reader`, so no bytes are skipped by reading around it
+        if self.reader.capacity() == 0 {
+            let cap = min(end.saturating_sub(start), DEFAULT_SIZE as u64) as usize;
+            let mut reader = BufReader::with_capacity(cap, self.reader.get_mut());
+            return read_line_forward(&mut reader, line_ending, cr_handling, start, end, line);
+        }
+
+        read_line_forward(&mut self.reader, line_ending, cr_handling, start, end, line)
+    }
+
+    /// Position of the first line starting at or after `target`, no further than `end`
+    fn line_start_from(&mut self, target: u64, end: u64) -> io::Result<u64> {
+        // A terminator is at most two bytes long, so start early enough to see one
+        // ending right at `target`, or to see that `target` falls within one
+        let mut start = target.saturating_sub(2);
+        let mut line = Vec::new();
+
+        while start < target {
+            let next = self.read_line_forward(start, end, &mut line)?;
+            if next == start {
+                break;
+            }
+            start = next;
+        }
+
+        Ok(start)
+    }
+
+    /// Forget all iteration state so the next read starts over from the end of the reader
+    fn reset(&mut self) {
+        self.reader_cursor = u64::MAX;
+        self.buffer_end = 0;
+        self.read_len = 0;
+        self.was_last_byte_line_feed = false;
+        self.pending_terminator = 0;
+        self.lines_read = 0;
+        self.pushed_back = None;
+        self.lookahead = None;
+        self.front_cursor = 0;
+    }
+
+    fn init_reader(&mut self) -> io::Result<()> {
+        // Move cursor to the end of the file
+        let end = self.reader_len()?;
+        if self.end_position.is_some() {
+            self.reader.seek(SeekFrom::Start(end))?;
+        }
+
+        self.init_reader_at(end, true)
+    }
+
+    /// Start reading backwards from `end`, where the reader is positioned. Unless
+    /// `end` is the end of the reader, the terminator right before it is always skipped
+    fn init_reader_at(&mut self, end: u64, at_end: bool) -> io::Result<()> {
+        // Store the cursor position
+        self.reader_cursor = end;
+        self.reader_end = end;
+        // Next read will be the full buffer size or the remaining bytes in the file
+        self.read_len = self.read_len_before(self.reader_cursor);
+        // Move cursor just before the next bytes to read
+        self.reader.seek_relative(-(self.read_len as i64))?;
+        // Update the cursor position
+        self.reader_cursor -= self.read_len as u64;
+
+        self.read_to_buffer()?;
+
+        // Handle any trailing line terminator of the reader
+        // so the first next call does not return Some("")
+        if self.buffer_end > 0 && !self.fixed_width {
+            let last_index = self.buffer_end - 1;
+
+            let unterminated = at_end && self.terminator_len_at(last_index)? == 0;
+
+            if unterminated && self.require_trailing_newline && !self.assume_trailing_newline {
+                // Reject the whole reader, nothing is left to read afterwards
+                self.reader_cursor = 0;
+                self.read_len = 0;
+                self.buffer_end = 0;
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "reader does not end in a line terminator",
+                ));
+            }
+            if !at_end || self.trailing_newline_policy != TrailingNewlinePolicy::Keep {
+                self.pending_terminator = self.terminator_len_at(last_index)?;
+            }
+            if at_end
+                && self.pending_terminator == 0
+                && self.trim_trailing_cr
+                && self.buffer[last_index] == CR_BYTE
+            {
+                self.pending_terminator = 1;
+            }
+            if unterminated
+                && self.pending_terminator == 0
+                && self.assume_trailing_newline
+                && self.trailing_newline_policy == TrailingNewlinePolicy::Keep
+            {
+                // The empty line after the assumed terminator is returned before any other
+                self.lookahead = Some(ScannedLine {
+                    offset: end,
+                    bytes: Vec::new(),
+                    terminator: Terminator::default(),
+                    len: 0,
+                    truncated: false,
+                });
+            }
+        }
+        self.empty_lines_to_skip = match self.trailing_newline_policy {
+            _ if !at_end => 0,
+            TrailingNewlinePolicy::TrimAll => usize::MAX,
+            _ => self.trim_trailing_empty,
+        };
+
+        Ok(())
+    }
+
+    /// Length of the line terminator ending with the byte at `index` of the buffer, 0 if none does
+    fn terminator_len_at(&mut self, index: usize) -> io::Result<usize> {
+        let byte = self.buffer[index];
+
+        if self.ends_unicode_break(byte)
+            && self.byte_before(index, 1)? == Some(UNICODE_BREAK_PREFIX[1])
+            && self.byte_before(index, 2)? == Some(UNICODE_BREAK_PREFIX[0])
+        {
+            return Ok(3);
+        }
+        if !self.line_ending.ends_terminator(byte) {
+            return Ok(0);
+        }
+        if self.line_ending != LineEnding::CrLf {
+            return Ok(1);
+        }
+
+        Ok(if self.byte_before(index, 1)? == Some(CR_BYTE) {
+            2
+        } else {
+            0
+        })
+    }
+
+    /// Whether `byte` can be the last byte of a Unicode line or paragraph separator
+    fn ends_unicode_break(&self, byte: u8) -> bool {
+        self.unicode_line_breaks && (byte == LS_LAST_BYTE || byte == PS_LAST_BYTE)
+    }
+
+    /// The byte `n` bytes in front of the byte at `index` of the buffer, if any
+    fn byte_before(&mut self, index: usize, n: usize) -> io::Result<Option<u8>> {
+        match index.checked_sub(n) {
+            Some(index) => Ok(Some(self.buffer[index])),
+            None => self.byte_before_buffer(n - index),
+        }
+    }
+
+    /// Peek at the byte `n` bytes in front of the buffer, so 1 is the last byte of the next read
+    fn byte_before_buffer(&mut self, n: usize) -> io::Result<Option<u8>> {
+        if self.reader_cursor + (self.read_len as u64) < n as u64 {
+            return Ok(None);
+        }
+
+        let mut byte = [0];
+        self.reader.seek_relative(self.read_len as i64 - n as i64)?;
+        self.reader.read_exact(&mut byte)?;
+        // Move back just in front of the next read
+        self.reader
+            .seek_relative(-(self.read_len as i64 - n as i64 + 1))?;
+
+        Ok(Some(byte[0]))
+    }
+
+    fn read_to_buffer(&mut self) -> io::Result<()> {
+        loop {
+            // Read the next bytes into the buffer, self.read_len was already prepared for that
+            let read = self.read_available()?;
+
+            if read < self.read_len {
+                let read_end = self.reader_cursor + read as u64;
+
+                // Bytes after this read were returned already, so the reader shrank since
+                if self.reader_cursor + (self.read_len as u64) < self.reader_end {
+                    return Err(io::Error::new(
+                        io::ErrorKind::UnexpectedEof,
+                        format!(
+                            "reverse cursor underflow: the reader ended at byte {read_end}, \
+                             but bytes up to {} were read before",
+                            self.reader_end
+                        ),
+                    ));
+                }
+                // Nothing was read yet, the reader is just shorter than it reported
+                self.reader_end = read_end;
+            }
+
+            // Determine what the next read length will be
+            let next_read_len = self.read_len_before(self.reader_cursor);
+            // Move the cursor just in front of the next read.
+            // BufReader::seek_relative only reuses its internal buffer when the target stays
+            // inside it and otherwise discards it before seeking, so no stale bytes can be read
+            self.reader
+                .seek_relative(-((read + next_read_len) as i64))?;
+            // Specify which part of the buffer is valid. Nothing is updated before the read and
+            // the seek succeeded, so after an error the same read can be retried from `reader_cursor`
+            self.buffer_end = read;
+            // Update cursor position
+            self.reader_cursor -= next_read_len as u64;
+
+            // Store the next read length, it'll be used in the next call
+            let requested = std::mem::replace(&mut self.read_len, next_read_len);
+
+            if let Some(progress) = self.progress.as_mut().filter(|_| read > 0) {
+                // Everything from the start of the bytes just read to the end was read
+                let processed = self.reader_end - self.reader_cursor - self.read_len as u64;
+                progress(processed, self.reader_end);
+            }
+
+            // An empty buffer means the start of the reader was reached, so keep going
+            // when the reader ended early and none of the requested bytes were there
+            if read > 0 || requested == 0 {
+                return Ok(());
+            }
+        }
+    }
+
+    /// Length of the next read, which ends at `end`. It is the full buffer size or the
+    /// remaining bytes in the reader, shortened so the read starts on the read alignment
+    fn read_len_before(&self, end: u64) -> usize {
+        let len = min(self.buffer.len() as u64, end);
+
+        if self.read_alignment > 1 {
+            let start = (end - len).next_multiple_of(self.read_alignment);
+            // No aligned start is in reach when the buffer is smaller than the alignment
+            if start < end {
+                return (end - start) as usize;
+            }
+        }
+
+        len as usize
+    }
+
+    /// Read up to `read_len` bytes into the buffer, stopping early if the reader ends.
+    ///
+    /// Some readers, such as files in `/proc` or on network filesystems, report a length
+    /// they can't deliver. Bytes past the actual end are treated as not being there.
+    fn read_available(&mut self) -> io::Result<usize> {
+        let mut read = 0;
+
+        while read < self.read_len {
+            match self.reader.read(&mut self.buffer[read..self.read_len]) {
+                Ok(0) => break,
+                Ok(n) => read += n,
+                Err(error) if error.kind() == io::ErrorKind::Interrupted => {}
+                Err(error) => return Err(error),
+            }
+        }
+
+        Ok(read)
+    }
+
+    fn next_line(&mut self) -> io::Result<Option<Vec<u8>>> {
+        Ok(self.next_line_at()?.map(|(_, line)| line))
+    }
+
+    /// Read the next line into `buf`, returning `false` once the reader is exhausted.
+    ///
+    /// `buf` is cleared before the line is written to it, so passing the same buffer
+    /// on every call avoids allocating once it has grown to fit the longest line.
+    pub fn read_line_into(&mut self, buf: &mut Vec<u8>) -> io::Result<bool> {
+        Ok(self.read_line_at(buf)?.is_some())
+    }
+
+    /// Read the next line into the internal line buffer and borrow it.
+    ///
+    /// The line buffer is cleared between lines and reused, so no allocation happens
+    /// once it has grown to fit the longest line. See [`RawRevLines::with_buffers`] to
+    /// supply it up front.
+    pub fn read_line(&mut self) -> io::Result<Option<&[u8]>> {
+        let mut line = std::mem::take(&mut self.line_buf);
+        let result = self.read_line_at(&mut line);
+        self.line_buf = line;
+
+        Ok(result?.map(|_| &self.line_buf[..]))
+    }
+
+    /// Find the last line containing `needle`, returning the absolute offset of its first
+    /// byte along with the line.
+    ///
+    /// Lines are read backwards from the current position only until the first match, so
+    /// iteration can carry on with the lines in front of it afterwards.
+    pub fn find_last_matching(&mut self, needle: &[u8]) -> io::Result<Option<(u64, Vec<u8>)>> {
+        let finder = memmem::Finder::new(needle);
+        let mut line = Vec::new();
+
+        while let Some(offset) = self.read_line_at(&mut line)? {
+            if finder.find(&line).is_some() {
+                return Ok(Some((offset, line)));
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Whether one of the next `max_lines` lines is exactly `needle`, e.g. to check if a
+    /// record was already written to the end of a log.
+    ///
+    /// Lines are compared as raw bytes and reading stops at the first match.
+    pub fn contains_line(&mut self, needle: &[u8], max_lines: usize) -> io::Result<bool> {
+        let mut line = std::mem::take(&mut self.line_buf);
+        let mut found = false;
+
+        for _ in 0..max_lines {
+            match self.read_line_at(&mut line) {
+                Ok(Some(_)) if line == needle => {
+                    found = true;
+                    break;
+                }
+                Ok(Some(_)) => {}
+                Ok(None) => break,
+                Err(error) => {
+                    self.line_buf = line;
+                    return Err(error);
+                }
+            }
+        }
+
+        self.line_buf = line;
+        Ok(found)
+    }
+
+    /// Returns the valid part of the buffer, `buffer_end` and `reader_cursor`,
+    /// to inspect how lines are scanned with a given capacity.
+    ///
+    /// `reader_cursor` is the position of the next read, which ends where the buffer
+    /// starts. Only available with the `debug-internals` feature.
+    #[cfg(any(test, feature = "debug-internals"))]
+    pub fn buffer_snapshot(&self) -> (&[u8], usize, u64) {
+        (
+            &self.buffer[..self.buffer_end],
+            self.buffer_end,
+            self.reader_cursor,
+        )
+    }
+
+    /// Recover from an error while reading a line by moving the reader back in front of
+    /// the next read, then skip the rest of the line which failed.
+    fn skip_failed_line(&mut self) -> io::Result<()> {
+        // The reader was never initialized, so no line was started yet
+        if self.reader_cursor == u64::MAX {
+            return Ok(());
+        }
+
+        self.reader.seek(SeekFrom::Start(self.reader_cursor))?;
+        let mut line = std::mem::take(&mut self.line_buf);
+        let result = self.read_line_at(&mut line);
+        self.line_buf = line;
+
+        result.map(|_| ())
+    }
+
+    /// Whether every byte up to the start of the reader was scanned
+    fn at_start(&self) -> bool {
+        self.reader_cursor == 0 && self.read_len == 0 && self.buffer_end == 0
+    }
+
+    /// Absolute position in the reader of the end of the bytes that have not been scanned yet
+    fn scan_position(&self) -> u64 {
+        self.reader_cursor + self.read_len as u64 + self.buffer_end as u64
+    }
+
+    /// Same as `next_line`, but also returns the absolute offset of the first byte of the line
+    fn next_line_at(&mut self) -> io::Result<Option<(u64, Vec<u8>)>> {
+        let mut line = Vec::new();
+
+        Ok(self.read_line_at(&mut line)?.map(|offset| (offset, line)))
+    }
+
+    /// Read the first line which was not read yet from the front, along with its offset.
+    /// Returns `None` once it meets the lines read from the back
+    fn next_line_back_at(&mut self) -> io::Result<Option<(u64, Vec<u8>)>> {
+        let end = match RawRevLines::position(self) {
+            Position(u64::MAX) => self.reader_len()?,
+            Position(end) => end,
+        };
+        if self.front_cursor >= end {
+            return Ok(None);
+        }
+        // Lines read forward are only split on the line ending, so with these options both
+        // ends could yield the same bytes, or lines which don't match
+        if self.unicode_line_breaks
+            || self.keep_terminators
+            || self.join_continuations
+            || self.fixed_width
+        {
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "lines can't be read from the front with unicode_line_breaks, \
+                 keep_terminators, join_continuations or fixed width records",
+            ));
+        }
+
+        let offset = self.front_cursor;
+        let mut line = Vec::new();
+        let result = self.read_line_forward(offset, end, &mut line);
+        self.restore_reader_position()?;
+        self.front_cursor = result?;
+
+        Ok(Some((offset, line)))
+    }
+
+    /// Read the next line into `line`, returning the absolute offset of its first byte
+    fn read_line_at(&mut self, line: &mut Vec<u8>) -> io::Result<Option<u64>> {
+        if let Some((offset, pushed_back)) = self.pushed_back.take() {
+            *line = pushed_back;
+            self.last_offset = offset;
+            self.lines_read += 1;
+            return Ok(Some(offset));
+        }
+        // The limit may have been set after more lines than it allows were read
+        if matches!(self.max_lines, Some(n) if self.lines_read >= n) {
+            return Ok(None);
+        }
+
+        // Initializing may already leave a line to return first
+        self.fill_to_capacity()?;
+
+        loop {
+            let offset = match self.lookahead.take() {
+                Some(scanned) => Some(scanned.restore(self, line)),
+                None => self.scan_line_at(line)?,
+            };
+            // Joined lines end where the last of them does
+            let end = offset.map_or(0, |offset| offset + self.line_len as u64);
+            let offset = match offset {
+                Some(offset) if self.join_continuations => Some(self.join_continued(line, offset)?),
+                offset => offset,
+            };
+
+            // The line was already read from the front
+            if matches!(offset, Some(offset) if offset < self.front_cursor) {
+                return Ok(None);
+            }
+
+            // A truncated line may be empty without having been empty in the reader
+            if self.empty_lines_to_skip > 0
+                && offset.is_some()
+                && line.is_empty()
+                && !self.line_truncated
+            {
+                self.empty_lines_to_skip -= 1;
+                continue;
+            }
+
+            if self.skip_null_runs {
+                line.dedup_by(|a, b| *a == 0 && *b == 0);
+            }
+            if self.cr_handling == CrHandling::StripAll {
+                line.retain(|byte| *byte != CR_BYTE);
+            }
+            if self.normalize_endings {
+                while line.last() == Some(&CR_BYTE) {
+                    line.pop();
+                }
+            }
+            if self.normalize_endings && self.convert_lone_cr {
+                line.iter_mut()
+                    .filter(|byte| **byte == CR_BYTE)
+                    .for_each(|byte| *byte = LF_BYTE);
+            }
+
+            self.empty_lines_to_skip = 0;
+            if offset.is_some() && self.keep_terminators {
+                line.extend_from_slice(self.terminator.as_bytes());
+            }
+            if let Some(offset) = offset {
+                self.last_offset = offset;
+                self.last_end = end;
+                self.lines_read += 1;
+            }
+            return Ok(offset);
+        }
+    }
+
+    /// Prepend the lines in front of `line` which end in a backslash, returning the offset
+    /// of the first one. The line after them is kept to be returned next
+    fn join_continued(&mut self, line: &mut Vec<u8>, mut offset: u64) -> io::Result<u64> {
+        let terminator = self.terminator;
+        let mut line_len = self.line_len;
+        let mut line_truncated = self.line_truncated;
+
+        loop {
+            let mut previous = Vec::new();
+            let Some(previous_offset) = self.scan_line_at(&mut previous)? else {
+                break;
+            };
+
+            if previous.last() != Some(&b'\\') {
+                self.lookahead = Some(ScannedLine::save(self, previous_offset, previous));
+                break;
+            }
+
+            previous.pop();
+            previous.append(line);
+            *line = previous;
+            offset = previous_offset;
+            line_len += self.line_len - 1;
+            line_truncated |= self.line_truncated;
+        }
+
+        self.terminator = terminator;
+        self.line_len = line_len;
+        self.line_truncated = line_truncated;
+        Ok(offset)
+    }
+
+    /// Scan backwards for the next line, ignoring the trailing newline policy
+    fn scan_line_at(&mut self, line: &mut Vec<u8>) -> io::Result<Option<u64>> {
+        // Reader cursor will only ever be u64::MAX if the reader has not been initialized
+        // If by some chance the reader is initialized with a file of length u64::MAX this will still work,
+        // as some read length value is subtracted from the cursor position right away
+        self.fill_to_capacity()?;
+
+        // For most sane scenarios, where size of the buffer is greater than the length of the line,
+        // the line is found in a single chunk of the buffer and copied as one subslice,
+        // which is very performant on modern architectures. See `push_chunk` for longer lines.
+        let mut chunks = 0;
+        // Whether the terminator or any bytes of the line were looked at. A terminator
+        // always ends a line, even an empty one at the very start of the reader
+        let mut scanned = false;
+        // Lines which run up to the start of the reader begin at offset 0
+        let mut offset = 0;
+        let mut refills = 0;
+
+        line.clear();
+        self.line_truncated = false;
+        self.line_len = 0;
+        self.terminator = Terminator::default();
+
+        // Reads are aligned to the width, so every buffer holds exactly one record
+        if self.fixed_width {
+            if self.buffer_end == 0 {
+                self.read_to_buffer()?;
+            }
+            if self.buffer_end == 0 {
+                return Ok(None);
+            }
+
+            self.line_len = self.buffer_end;
+            self.line_truncated = push_chunk(
+                line,
+                &mut chunks,
+                &self.buffer[..self.buffer_end],
+                self.truncate_len,
+            );
+            self.buffer_end = 0;
+            return Ok(Some(self.scan_position()));
+        }
+
+        // Runs of empty lines are common in some inputs, so when the terminator of this line
+        // and the one in front of it are next to each other in the buffer, skip the scan.
+        // A \r in front may be part of a \r\n terminator and takes the regular path
+        if self.pending_terminator == 1
+            && self.buffer_end >= 2
+            && self.line_ending != LineEnding::CrLf
+        {
+            let previous = self.buffer[self.buffer_end - 2];
+
+            if previous != CR_BYTE && self.line_ending.ends_terminator(previous) {
+                self.buffer_end -= 1;
+                self.terminator.push_front(self.buffer[self.buffer_end]);
+                return Ok(Some(self.scan_position()));
+            }
+        }
+
+        loop {
+            // Current buffer was read to completion, read new contents
+            if self.buffer_end == 0 {
+                if self.read_len > 0 {
+                    if self.max_refills == Some(refills) {
+                        return Err(io::Error::new(
+                            io::ErrorKind::InvalidData,
+                            format!("line needs more than {refills} buffer refills"),
+                        ));
+                    }
+                    refills += 1;
+                }
+
+                // Read the of minimum between the desired
+                // buffer size or remaining length of the reader
+                self.read_to_buffer()?;
+            }
+
+            // If buffer_end is still 0, it means the reader is empty
+            if self.buffer_end == 0 {
+                if scanned {
+                    break;
+                } else {
+                    return Ok(None);
+                }
+            }
+
+            // Skip the terminator of this line, which may span several buffers
+            if self.pending_terminator > 0 {
+                let skipped = min(self.pending_terminator, self.buffer_end);
+                scanned = true;
+
+                self.pending_terminator -= skipped;
+                self.buffer_end -= skipped;
+                for index in (self.buffer_end..self.buffer_end + skipped).rev() {
+                    self.terminator.push_front(self.buffer[index]);
+                }
+                self.was_last_byte_line_feed = self.pending_terminator == 0
+                    && match self.line_ending {
+                        LineEnding::Lf => self.cr_handling != CrHandling::Keep,
+                        LineEnding::Auto | LineEnding::ControlChars => true,
+                        _ => false,
+                    }
+                    && self.buffer[self.buffer_end] == LF_BYTE;
+                continue;
+            }
+
+            scanned = true;
+
+            // If the terminator was a line feed, skip a carriage return right before it
+            if self.was_last_byte_line_feed {
+                self.was_last_byte_line_feed = false;
+
+                if self.buffer[self.buffer_end - 1] == CR_BYTE {
+                    self.buffer_end -= 1;
+                    self.terminator.push_front(CR_BYTE);
+                    continue;
+                }
+            }
+
+            let buffer_length = self.buffer_end;
+            let mut search_end = self.buffer_end;
+
+            // Find the terminator of the previous line. A byte which can end a terminator
+            // might not actually end one, e.g. a bare line feed when only \r\n ends lines
+            let terminator = loop {
+                let line_ending = self.line_ending;
+                let Some(index) = self.buffer[..search_end].iter().rposition(|byte| {
+                    line_ending.ends_terminator(*byte) || self.ends_unicode_break(*byte)
+                }) else {
+                    break None;
+                };
+
+                match self.terminator_len_at(index)? {
+                    0 => search_end = index,
+                    len => break Some((index, len)),
+                }
+            };
+
+            match terminator {
+                Some((index, len)) => {
+                    self.line_len += buffer_length - index - 1;
+                    self.line_truncated |= push_chunk(
+                        line,
+                        &mut chunks,
+                        &self.buffer[index + 1..buffer_length],
+                        self.truncate_len,
+                    );
+                    // Leave the terminator to be skipped by the next call
+                    self.buffer_end = index + 1;
+                    self.pending_terminator = len;
+                    offset = self.scan_position();
+                    self.check_line_len(line)?;
+                    break;
+                }
+                None => {
+                    self.line_len += buffer_length;
+                    self.line_truncated |= push_chunk(
+                        line,
+                        &mut chunks,
+                        &self.buffer[..buffer_length],
+                        self.truncate_len,
+                    );
+                    self.buffer_end = 0;
+                    self.check_line_len(line)?;
+                }
+            }
+        }
+
+        if chunks > 1 {
+            line.reverse();
+        }
+
+        Ok(Some(offset))
+    }
+
+    /// Fail and discard `line` if the line scanned so far is longer than `max_line_len`
+    fn check_line_len(&self, line: &mut Vec<u8>) -> io::Result<()> {
+        match self.max_line_len {
+            Some(limit) if self.line_len > limit => {
+                line.clear();
+                Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("line is longer than {limit} bytes"),
+                ))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Check whether the line feed at `offset` sits inside a malformed multibyte UTF-8 sequence,
+    /// i.e. it follows a lead byte that is still missing continuation bytes and is itself
+    /// followed by a continuation byte.
+    fn line_feed_splits_sequence(&mut self, offset: u64) -> io::Result<bool> {
+        let start = offset.saturating_sub(3);
+        let mut window = [0; 7];
+        let mut window_len = 0;
+        // Bytes after the end iteration started from are not part of the reader
+        let window_cap = min(window.len() as u64, self.reader_end.saturating_sub(start)) as usize;
+
+        self.reader.seek(SeekFrom::Start(start))?;
+        while window_len < window_cap {
+            match self.reader.read(&mut window[window_len..window_cap])? {
+                0 => break,
+                n => window_len += n,
+            }
+        }
+        // Put the reader back where the next buffer read expects it
+        self.reader.seek(SeekFrom::Start(self.reader_cursor))?;
+
+        let line_feed_index = (offset - start) as usize;
+        if window_len <= line_feed_index + 1 || window[line_feed_index] != LF_BYTE {
+            return Ok(false);
+        }
+
+        let before = &window[..line_feed_index];
+        let is_continuation = |byte: u8| byte & 0b1100_0000 == 0b1000_0000;
+
+        if !is_continuation(window[line_feed_index + 1]) {
+            return Ok(false);
+        }
+
+        // Walk back over continuation bytes to the lead byte of the last sequence
+        let Some(lead_index) = before.iter().rposition(|byte| !is_continuation(*byte)) else {
+            return Ok(false);
+        };
+        let width = match before[lead_index] {
+            0xC2..=0xDF => 2,
+            0xE0..=0xEF => 3,
+            0xF0..=0xF4 => 4,
+            _ => return Ok(false),
+        };
+
+        Ok(before.len() - lead_index < width)
+    }
+}
+
+impl<T: AsRef<[u8]>> RawRevLines<Cursor<T>> {
+    /// Create a new `RawRevLines` struct from an in-memory reader.
+    ///
+    /// The bytes are already in memory, so unlike [`RawRevLines::new`] they are copied
+    /// straight from the cursor into the buffer, without going through a `BufReader`.
+    pub fn from_cursor(cursor: Cursor<T>) -> RawRevLines<Cursor<T>> {
+        RawRevLines::from_cursor_with_capacity(DEFAULT_SIZE, cursor)
+    }
+
+    /// Same as [`RawRevLines::from_cursor`], reading `cap` bytes at a time.
+    pub fn from_cursor_with_capacity(cap: usize, cursor: Cursor<T>) -> RawRevLines<Cursor<T>> {
+        // A BufReader without capacity passes every read and seek straight to the cursor
+        RawRevLines::from_parts(
+            vec![0; cap],
+            Vec::new(),
+            BufReader::with_capacity(0, cursor),
+        )
+    }
+}
+
+/// Bytes of a line terminator, collected back to front while it is skipped
+#[derive(Clone, Copy)]
+struct Terminator {
+    // Long enough for a Unicode line separator
+    bytes: [u8; 3],
+    start: usize,
+}
+
+impl Default for Terminator {
+    fn default() -> Terminator {
+        Terminator {
+            bytes: [0; 3],
+            start: 3,
+        }
+    }
+}
+
+impl Terminator {
+    fn push_front(&mut self, byte: u8) {
+        self.start -= 1;
+        self.bytes[self.start] = byte;
+    }
+
+    fn as_bytes(&self) -> &[u8] {
+        &self.bytes[self.start..]
+    }
+}
+
+/// A line scanned ahead of the one being returned, with the scan state describing it
+struct ScannedLine {
+    offset: u64,
+    bytes: Vec<u8>,
+    terminator: Terminator,
+    len: usize,
+    truncated: bool,
+}
+
+impl ScannedLine {
+    fn save<R>(rev_lines: &RawRevLines<R>, offset: u64, bytes: Vec<u8>) -> ScannedLine {
+        ScannedLine {
+            offset,
+            bytes,
+            terminator: rev_lines.terminator,
+            len: rev_lines.line_len,
+            truncated: rev_lines.line_truncated,
+        }
+    }
+
+    /// Make this the line read last, returning its offset
+    fn restore<R>(self, rev_lines: &mut RawRevLines<R>, line: &mut Vec<u8>) -> u64 {
+        *line = self.bytes;
+        rev_lines.terminator = self.terminator;
+        rev_lines.line_len = self.len;
+        rev_lines.line_truncated = self.truncated;
+        self.offset
+    }
+
+    /// Absolute position of the end of the terminator after the line
+    fn end(&self) -> u64 {
+        self.offset + self.len as u64 + self.terminator.as_bytes().len() as u64
+    }
+}
+
+/// Read the line starting at `start` forward from `reader`, which is positioned there,
+/// into `line`. Returns the position after its terminator, where the next line starts
+fn read_line_forward<B: BufRead>(
+    reader: &mut B,
+    line_ending: LineEnding,
+    cr_handling: CrHandling,
+    start: u64,
+    end: u64,
+    line: &mut Vec<u8>,
+) -> io::Result<u64> {
+    let mut position = start;
+
+    line.clear();
+
+    while position < end {
+        let available = reader.fill_buf()?;
+        let available = &available[..min(available.len() as u64, end - position) as usize];
+        if available.is_empty() {
+            break;
+        }
+
+        let Some(index) = available
+            .iter()
+            .position(|byte| line_ending.ends_terminator(*byte))
+        else {
+            let len = available.len();
+            line.extend_from_slice(available);
+            reader.consume(len);
+            position += len as u64;
+            continue;
+        };
+
+        let byte = available[index];
+        line.extend_from_slice(&available[..index]);
+        reader.consume(index + 1);
+        position += index as u64 + 1;
+
+        match line_ending {
+            // A bare line feed does not end the line
+            LineEnding::CrLf if line.last() != Some(&CR_BYTE) => {
+                line.push(byte);
+                continue;
+            }
+            LineEnding::CrLf => {
+                line.pop();
+            }
+            LineEnding::Lf if cr_handling != CrHandling::Keep && line.last() == Some(&CR_BYTE) => {
+                line.pop();
+            }
+            _ => {}
+        }
+
+        // A \r may be the start of a \r\n terminator
+        let may_continue = matches!(line_ending, LineEnding::Auto | LineEnding::ControlChars)
+            && byte == CR_BYTE
+            && position < end;
+        if may_continue && reader.fill_buf()?.first() == Some(&LF_BYTE) {
+            reader.consume(1);
+            position += 1;
+        }
+        break;
+    }
+
+    if cr_handling == CrHandling::StripAll {
+        line.retain(|byte| *byte != CR_BYTE);
+    }
+
+    Ok(position)
+}
+
+/// Push a chunk of a line that is being read backward. Once `limit` bytes have been
+/// collected only the part of the chunk closest to the end of the line is kept.
+/// Returns whether any bytes were dropped.
+///
+/// The first chunk is copied as is. Once a line spans a second chunk, the line is
+/// collected in reverse byte order instead, so earlier chunks can be appended rather
+/// than inserted at the front, and is flipped back by the caller once complete.
+fn push_chunk(line: &mut Vec<u8>, chunks: &mut usize, chunk: &[u8], limit: Option<usize>) -> bool {
+    let keep = match limit {
+        Some(limit) => min(chunk.len(), limit.saturating_sub(line.len())),
+        None => chunk.len(),
+    };
+    let start = line.len();
+
+    if *chunks == 1 {
+        line.reverse();
+    }
+    line.extend_from_slice(&chunk[chunk.len() - keep..]);
+    if *chunks > 0 {
+        line[start..].reverse();
+    }
+    *chunks += 1;
+
+    keep < chunk.len()
+}
+
+impl<R: Read + Seek> Iterator for RawRevLines<R> {
+    type Item = io::Result<Vec<u8>>;
+
+    fn next(&mut self) -> Option<io::Result<Vec<u8>>> {
+        self.next_line().transpose()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let max_lines = self.max_lines.map(|n| n.saturating_sub(self.lines_read));
+        // Every line left takes at least a byte of the reader, apart from a line given
+        // back or scanned ahead, and the empty line after the last terminator when it is
+        // kept. The length of the reader is unknown until the first read
+        let remaining = match self.reader_cursor {
+            u64::MAX => None,
+            _ => usize::try_from(self.scan_position().saturating_sub(self.front_cursor))
+                .ok()
+                .map(|bytes| {
+                    bytes
+                        + usize::from(self.pushed_back.is_some())
+                        + usize::from(self.lookahead.is_some())
+                        + usize::from(self.trailing_newline_policy == TrailingNewlinePolicy::Keep)
+                }),
+        };
+
+        match (max_lines, remaining) {
+            (Some(max_lines), Some(remaining)) => (0, Some(min(max_lines, remaining))),
+            (max_lines, remaining) => (0, max_lines.or(remaining)),
+        }
+    }
+}
+
+/// Reads lines forward from the start of the reader, until they meet the lines read
+/// backwards with `next`.
+///
+/// Lines read from the front only have their line ending and carriage returns handled.
+/// Options which alter or skip lines, like [`RawRevLines::max_lines`], apply to
+/// the lines read from the back alone. Options which change where lines split or what
+/// they contain, [`RawRevLines::unicode_line_breaks`], [`RawRevLines::keep_terminators`],
+/// [`RawRevLines::join_continuations`] and [`RawRevLines::with_fixed_width`], make
+/// `next_back` fail with an `io::Error` of kind `Unsupported` instead.
+///
+/// ```
+/// use std::io::Cursor;
+///
+/// use rev_lines::RawRevLines;
+///
+/// let mut rev_lines = RawRevLines::new(Cursor::new("A\nB\nC\n"));
+///
+/// assert_eq!(rev_lines.next().unwrap().unwrap(), b"C");
+/// assert_eq!(rev_lines.next_back().unwrap().unwrap(), b"A");
+/// assert_eq!(rev_lines.next().unwrap().unwrap(), b"B");
+/// assert!(rev_lines.next().is_none());
+/// ```
+impl<R: Read + Seek> DoubleEndedIterator for RawRevLines<R> {
+    fn next_back(&mut self) -> Option<io::Result<Vec<u8>>> {
+        self.next_line_back_at()
+            .transpose()
+            .map(|line| line.map(|(_, line)| line))
+    }
+}
+
+/// Bookmark returned by [`RawRevLines::position`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Position(u64);
+
+/// Line count and length distribution returned by [`RawRevLines::stats`]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct LineStats {
+    pub count: usize,
+    pub min_len: usize,
+    pub max_len: usize,
+    pub total_bytes: u64,
+}
+
+impl LineStats {
+    /// Mean line length in bytes, `None` if there were no lines
+    pub fn mean_len(&self) -> Option<f64> {
+        (self.count > 0).then(|| self.total_bytes as f64 / self.count as f64)
+    }
+
+    fn push(&mut self, len: usize) {
+        self.min_len = if self.count == 0 {
+            len
+        } else {
+            min(self.min_len, len)
+        };
+        self.max_len = self.max_len.max(len);
+        self.total_bytes += len as u64;
+        self.count += 1;
+    }
+}
+
+// Without the default `thiserror` feature, `Display`, `Error` and `From<io::Error>` are
+// implemented by hand below, with the same messages
+#[derive(Debug)]
+#[cfg_attr(feature = "thiserror", derive(Error))]
+#[non_exhaustive]
+pub enum RevLinesError {
+    #[cfg_attr(feature = "thiserror", error(transparent))]
+    Io(#[cfg_attr(feature = "thiserror", from)] std::io::Error),
+    /// A line is not valid UTF-8. `offset` is the absolute position of the first byte
+    /// of the line in the reader.
+    #[cfg_attr(
+        feature = "thiserror",
+        error("invalid UTF-8 in line starting at byte offset {offset}")
+    )]
+    InvalidUtf8 {
+        offset: u64,
+        #[cfg_attr(feature = "thiserror", source)]
+        source: std::string::FromUtf8Error,
+    },
+    /// A line feed was found inside a malformed multibyte UTF-8 sequence.
+    /// Only reported when `RevLines::validate_boundaries` is enabled.
+    #[cfg_attr(
+        feature = "thiserror",
+        error("line feed at byte offset {offset} splits a malformed UTF-8 sequence")
+    )]
+    SplitSequence { offset: u64 },
+}
+
+#[cfg(not(feature = "thiserror"))]
+impl std::fmt::Display for RevLinesError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RevLinesError::Io(error) => std::fmt::Display::fmt(error, f),
+            RevLinesError::InvalidUtf8 { offset, .. } => {
+                write!(f, "invalid UTF-8 in line starting at byte offset {offset}")
+            }
+            RevLinesError::SplitSequence { offset } => write!(
+                f,
+                "line feed at byte offset {offset} splits a malformed UTF-8 sequence"
+            ),
+        }
+    }
+}
+
+#[cfg(not(feature = "thiserror"))]
+impl std::error::Error for RevLinesError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            // Transparent, like the derived impl
+            RevLinesError::Io(error) => error.source(),
+            RevLinesError::InvalidUtf8 { source, .. } => Some(source),
+            RevLinesError::SplitSequence { .. } => None,
+        }
+    }
+}
+
+#[cfg(not(feature = "thiserror"))]
+impl From<io::Error> for RevLinesError {
+    fn from(error: io::Error) -> RevLinesError {
+        RevLinesError::Io(error)
+    }
+}
+
+/// Converts into `RevLinesError::InvalidUtf8` with an offset of 0, so a [`LineDecoder`]
+/// can use `?`. `RevLines` fills in the offset of the line that failed to decode.
+impl From<std::string::FromUtf8Error> for RevLinesError {
+    fn from(source: std::string::FromUtf8Error) -> RevLinesError {
+        RevLinesError::InvalidUtf8 { offset: 0, source }
+    }
+}
+
+/// A reader which can also seek, usable as a trait object since `dyn Read + Seek`
+/// is not a valid type. Implemented for every `Read + Seek` type.
+///
+/// ```
+/// use std::io::Cursor;
+///
+/// use rev_lines::{ReadSeek, RevLines};
+///
+/// let readers: Vec<Box<dyn ReadSeek>> = vec![
+///     Box::new(Cursor::new("a\nb\n")),
+///     Box::new(Cursor::new(b"c\nd\n".to_vec())),
+/// ];
+///
+/// for reader in readers {
+///     let lines: Vec<String> = RevLines::new(reader).map(Result::unwrap).collect();
+///     assert_eq!(lines.len(), 2);
+/// }
+/// ```
+pub trait ReadSeek: Read + Seek {}
+
+impl<T: Read + Seek + ?Sized> ReadSeek for T {}
+
+/// What `RevLines` does when reading from the reader fails
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum IoErrorPolicy {
+    /// Yield the error. This is the default.
+    #[default]
+    Abort,
+    /// Skip the line which was being read when the error happened and carry on with the
+    /// line in front of it. Only errors of kind `WouldBlock`, `TimedOut` and `Interrupted`
+    /// are recovered from, others are yielded as with `Abort`. If recovering fails as well,
+    /// that error is yielded.
+    SkipLine,
+}
+
+impl IoErrorPolicy {
+    fn recovers_from(self, error: &io::Error) -> bool {
+        self == IoErrorPolicy::SkipLine
+            && matches!(
+                error.kind(),
+                io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut | io::ErrorKind::Interrupted
+            )
+    }
+}
+
+pub struct RevLines<R, D = Utf8Decoder> {
+    raw: RawRevLines<R>,
+    decoder: D,
+    validate_boundaries: bool,
+    io_error_policy: IoErrorPolicy,
+}
+
+impl<R: Read + Seek> RevLines<R> {
+    /// Create a new `RawRevLines` struct from a Reader.
+    /// Internal buffering for iteration will default to 4096 bytes at a time.
+    pub fn new(reader: R) -> RevLines<R> {
+        RevLines::from(RawRevLines::new(reader))
+    }
+
+    /// Create a new `RawRevLines` struct from a Reader`.
+    /// Internal buffering for iteration will use `cap` bytes at a time.
+    pub fn with_capacity(cap: usize, reader: R) -> RevLines<R> {
+        RevLines::from(RawRevLines::with_capacity(cap, reader))
+    }
+
+    /// Create a new `RevLines` which splits lines on `delim`.
+    /// See [`RawRevLines::with_delimiter`].
+    pub fn with_delimiter(delim: u8, reader: R) -> RevLines<R> {
+        RevLines::from(RawRevLines::with_delimiter(delim, reader))
+    }
+
+    /// Same as [`RevLines::with_delimiter`], reading `cap` bytes at a time.
+    pub fn with_capacity_and_delimiter(cap: usize, delim: u8, reader: R) -> RevLines<R> {
+        RevLines::from(RawRevLines::with_capacity_and_delimiter(cap, delim, reader))
+    }
+
+    /// Create a new `RevLines` which treats the current position of the reader as its end,
+    /// e.g. to read the lines in front of where a file was read forward up to.
+    ///
+    /// ```
+    /// use std::io::{Cursor, Seek, SeekFrom};
+    ///
+    /// use rev_lines::RevLines;
+    ///
+    /// let mut file = Cursor::new("first\nsecond\nthird\n");
+    /// file.seek(SeekFrom::Start(13)).unwrap();
+    ///
+    /// let lines: Vec<String> = RevLines::from_current_position(file)
+    ///     .unwrap()
+    ///     .map(Result::unwrap)
+    ///     .collect();
+    /// assert_eq!(lines, vec!["second", "first"]);
+    /// ```
+    pub fn from_current_position(reader: R) -> io::Result<RevLines<R>> {
+        Ok(RevLines::from(RawRevLines::from_current_position(reader)?))
+    }
+
+    /// Create a new `RevLines` which yields decoded records of `width` bytes instead of
+    /// lines. See [`RawRevLines::with_fixed_width`].
+    pub fn with_fixed_width(width: usize, reader: R) -> RevLines<R> {
+        RevLines::from(RawRevLines::with_fixed_width(width, reader))
+    }
+
+    /// Create a new `RevLines` which treats `offset` as the end of the reader.
+    /// See [`RawRevLines::with_start_offset`].
+    pub fn with_start_offset(offset: u64, reader: R) -> RevLines<R> {
+        RevLines::from(RawRevLines::with_start_offset(offset, reader))
+    }
+
+    /// Iterate over the lines of a reader in reverse, replacing invalid UTF-8 with `U+FFFD`
+    /// like [`Utf8LossyDecoder`], so only errors reading from the reader are yielded.
+    ///
+    /// ```
+    /// use std::io::Cursor;
+    ///
+    /// use rev_lines::RevLines;
+    ///
+    /// let lines: Vec<String> = RevLines::lossy(Cursor::new(b"ok\nbad \xff\n".to_vec()))
+    ///     .collect::<std::io::Result<_>>()
+    ///     .unwrap();
+    ///
+    /// assert_eq!(lines, vec!["bad \u{FFFD}", "ok"]);
+    /// ```
+    pub fn lossy(reader: R) -> impl Iterator<Item = io::Result<String>> {
+        RevLines::new(reader).into_lossy()
+    }
+
+    /// Same as [`RevLines::with_capacity`], with a capacity that can't be zero.
+    pub fn with_nonzero_capacity(cap: NonZeroUsize, reader: R) -> RevLines<R> {
+        RevLines::from(RawRevLines::with_nonzero_capacity(cap, reader))
+    }
+
+    /// Same as [`RevLines::with_capacity`], but returns an error instead of aborting
+    /// when the buffer of `cap` bytes can't be allocated.
+    pub fn try_with_capacity(cap: usize, reader: R) -> Result<RevLines<R>, TryReserveError> {
+        Ok(RevLines::from(RawRevLines::try_with_capacity(cap, reader)?))
+    }
+
+    /// Create an iterator over the last `n` lines of a Reader in their natural,
+    /// oldest-first order, like the default output of `tail`.
+    ///
+    /// Only enough of the reader to find `n` lines is read. The lines are read
+    /// in reverse on the first call to `next` and then handed out front to back.
+    pub fn tail_forward(reader: R, n: usize) -> TailForward<R> {
+        TailForward {
+            rev_lines: Some(RevLines::new(reader)),
+            n,
+            lines: Vec::new(),
+        }
+    }
+
+    /// Read the first and the last line of a Reader, without reading what lies between.
+    ///
+    /// The last line is found with a reverse scan from the end, the first by seeking to the
+    /// start and reading forward. For a reader with a single line both are the same line,
+    /// and for an empty reader both are `None`.
+    pub fn probe(reader: R) -> Result<(Option<String>, Option<String>), RevLinesError> {
+        let mut rev_lines = RevLines::new(reader);
+        let Some(last) = rev_lines.next().transpose()? else {
+            return Ok((None, None));
+        };
+
+        let mut first = Vec::new();
+        rev_lines.raw.reader.seek(SeekFrom::Start(0))?;
+        rev_lines.raw.reader.read_until(LF_BYTE, &mut first)?;
+
+        if first.last() == Some(&LF_BYTE) {
+            first.pop();
+            if first.last() == Some(&CR_BYTE) {
+                first.pop();
+            }
+        }
+
+        Ok((Some(rev_lines.decode(0, first)?), Some(last)))
+    }
+
+    /// Find the last `max_matches` lines of a reader containing `pattern`, last line first,
+    /// each with the absolute offset of its first byte.
+    ///
+    /// Lines are searched as raw bytes and only matching lines are decoded, so invalid
+    /// UTF-8 in other lines is not an error. Reading stops once enough lines were found.
+    ///
+    /// ```
+    /// use std::io::Cursor;
+    ///
+    /// use rev_lines::RevLines;
+    ///
+    /// let log = Cursor::new("ERROR a\nINFO b\nERROR c\nERROR d\n");
+    /// let matches = RevLines::grep(log, "ERROR", 2).unwrap();
+    ///
+    /// assert_eq!(matches, vec![(23, "ERROR d".to_string()), (15, "ERROR c".to_string())]);
+    /// ```
+    pub fn grep(
+        reader: R,
+        pattern: &str,
+        max_matches: usize,
+    ) -> Result<Vec<(u64, String)>, RevLinesError> {
+        let mut rev_lines = RevLines::new(reader);
+        let finder = memmem::Finder::new(pattern);
+        let mut matches = Vec::new();
+        let mut line = Vec::new();
+
+        while matches.len() < max_matches {
+            let Some(offset) = rev_lines.raw.read_line_at(&mut line)? else {
+                break;
+            };
+
+            if finder.find(&line).is_some() {
+                let line = rev_lines.decode(offset, std::mem::take(&mut line))?;
+                matches.push((offset, line));
+            }
+        }
+
+        Ok(matches)
+    }
+
+    /// Decode each line and pass it to `f`, yielding only the `Some` results.
+    ///
+    /// Lines are handed to `f` as a borrowed `&str` without allocating a `String`.
+    /// IO and UTF-8 errors are still yielded as they are encountered.
+    ///
+    /// ```
+    /// use std::io::Cursor;
+    ///
+    /// use rev_lines::RevLines;
+    ///
+    /// let file = Cursor::new("10\nnot a number\n20\n\n30\n");
+    /// let numbers = RevLines::new(file)
+    ///     .filter_map_lines(|line| line.parse::<u32>().ok())
+    ///     .collect::<Result<Vec<_>, _>>()
+    ///     .unwrap();
+    ///
+    /// assert_eq!(numbers, vec![30, 20, 10]);
+    /// ```
+    pub fn filter_map_lines<T, F>(
+        mut self,
+        mut f: F,
+    ) -> impl Iterator<Item = Result<T, RevLinesError>>
+    where
+        F: FnMut(&str) -> Option<T>,
+    {
+        std::iter::from_fn(move || loop {
+            let (offset, line) = match self.raw.next_line_at().transpose()? {
+                Ok(line) => line,
+                Err(error) => return Some(Err(RevLinesError::Io(error))),
+            };
+
+            match std::str::from_utf8(&line) {
+                Ok(line) => {
+                    if let Some(item) = f(line) {
+                        return Some(Ok(item));
+                    }
+                }
+                Err(_) => {
+                    if let Err(error) = self.decode(offset, line) {
+                        return Some(Err(error));
+                    }
+                }
+            }
+        })
+    }
+
+    /// Same as [`RevLines::lossy`], for an iterator which was already set up, e.g. with a
+    /// custom capacity or line ending. Any decoder set before is replaced.
+    pub fn into_lossy(self) -> impl Iterator<Item = io::Result<String>> {
+        self.with_decoder(Utf8LossyDecoder).map(|line| {
+            line.map_err(|error| match error {
+                RevLinesError::Io(error) => error,
+                // Lossy decoding does not fail, so this is never reached
+                error => io::Error::new(io::ErrorKind::InvalidData, error),
+            })
+        })
+    }
+
+    /// Decode lines lossily like [`Utf8LossyDecoder`], but also yield whether any invalid
+    /// bytes of the line were replaced with `U+FFFD`, e.g. to monitor data quality.
+    ///
+    /// ```
+    /// use std::io::Cursor;
+    ///
+    /// use rev_lines::RevLines;
+    ///
+    /// let file = Cursor::new(b"ok\nbad \xff\n".to_vec());
+    /// let mut lines = RevLines::new(file).lines_utf8_lossy_bytes();
+    ///
+    /// assert_eq!(lines.next().unwrap().unwrap(), ("bad \u{FFFD}".to_string(), true));
+    /// assert_eq!(lines.next().unwrap().unwrap(), ("ok".to_string(), false));
+    /// ```
+    pub fn lines_utf8_lossy_bytes(
+        self,
+    ) -> impl Iterator<Item = Result<(String, bool), RevLinesError>> {
+        // Lines are decoded strictly first, so only invalid lines pay for the lossy decoding
+        self.map(|line| match line {
+            Ok(line) => Ok((line, false)),
+            Err(RevLinesError::InvalidUtf8 { source, .. }) => {
+                Ok((Utf8LossyDecoder.decode_owned(source.into_bytes())?, true))
+            }
+            Err(error) => Err(error),
+        })
+    }
+}
+
+impl<T: AsRef<[u8]>> RevLines<Cursor<T>> {
+    /// Create a new `RevLines` struct from an in-memory reader. See [`RawRevLines::from_cursor`].
+    pub fn from_cursor(cursor: Cursor<T>) -> RevLines<Cursor<T>> {
+        RevLines::from(RawRevLines::from_cursor(cursor))
+    }
+
+    /// Create a new `RevLines` struct from an in-memory reader, reading `cap` bytes at a time.
+    pub fn from_cursor_with_capacity(cap: usize, cursor: Cursor<T>) -> RevLines<Cursor<T>> {
+        RevLines::from(RawRevLines::from_cursor_with_capacity(cap, cursor))
+    }
+}
+
+impl<'a> RevLines<Cursor<&'a [u8]>> {
+    /// Iterate over the lines of a string in reverse, yielding slices of it.
+    ///
+    /// The string is already valid UTF-8, so lines are neither copied nor validated.
+    ///
+    /// ```
+    /// use rev_lines::RevLines;
+    ///
+    /// let lines = RevLines::from_string("first\nsecond\n").collect::<Vec<_>>();
+    ///
+    /// assert_eq!(lines, vec!["second", "first"]);
+    /// ```
+    pub fn from_string(text: &'a str) -> StrRevLines<'a> {
+        let mut raw = RawRevLines::new(Cursor::new(text.as_bytes()));
+        // Lines are sliced out of `text` by their range, so no bytes need to be copied
+        raw.truncate_len = Some(0);
+
+        StrRevLines { text, raw }
+    }
+}
+
+impl RevLines<Cursor<Vec<u8>>> {
+    /// Iterate over the lines of an owned string in reverse.
+    pub fn from_owned_string(text: String) -> RevLines<Cursor<Vec<u8>>> {
+        RevLines::new(Cursor::new(text.into_bytes()))
+    }
+}
+
+impl RevLines<File> {
+    /// Open the file at `path` and iterate over its lines in reverse.
+    ///
+    /// ```
+    /// use rev_lines::RevLines;
+    ///
+    /// let mut rev_lines = RevLines::open("Cargo.toml").unwrap();
+    /// assert!(rev_lines.next().is_some());
+    /// ```
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<RevLines<File>> {
+        File::open(path).map(RevLines::new)
+    }
+}
+
+impl<R: Read + Seek, D: LineDecoder> RevLines<R, D> {
+    /// Fold `f` over the raw bytes of the next `n` lines. See [`RawRevLines::fold_bytes`].
+    pub fn fold_bytes<T, F>(&mut self, init: T, n: usize, f: F) -> io::Result<T>
+    where
+        F: FnMut(T, &[u8]) -> T,
+    {
+        self.raw.fold_bytes(init, n, f)
+    }
+
+    /// Strip every `\r` at the end of lines. See [`RawRevLines::normalize_endings`].
+    pub fn normalize_endings(mut self, normalize: bool) -> RevLines<R, D> {
+        self.raw = self.raw.normalize_endings(normalize);
+        self
+    }
+
+    /// Replace every `\r` within a line with `\n`. See [`RawRevLines::convert_lone_cr`].
+    pub fn convert_lone_cr(mut self, convert: bool) -> RevLines<R, D> {
+        self.raw = self.raw.convert_lone_cr(convert);
+        self
+    }
+
+    /// Set what happens when reading from the reader fails. Defaults to [`IoErrorPolicy::Abort`].
+    pub fn io_error_policy(mut self, policy: IoErrorPolicy) -> RevLines<R, D> {
+        self.io_error_policy = policy;
+        self
+    }
+
+    /// Start every read from the reader at a multiple of `alignment` bytes.
+    /// See [`RawRevLines::align_reads`].
+    pub fn align_reads(mut self, alignment: u64) -> RevLines<R, D> {
+        self.raw = self.raw.align_reads(alignment);
+        self
+    }
+
+    /// Call `cb` with `(bytes_processed, total_bytes)` every time the buffer was refilled.
+    /// See [`RawRevLines::progress`].
+    pub fn progress<F: FnMut(u64, u64) + Send + 'static>(mut self, cb: F) -> RevLines<R, D> {
+        self.raw = self.raw.progress(cb);
+        self
+    }
+
+    /// Stop after `n` lines, without reading any further from the reader.
+    /// See [`RawRevLines::max_lines`].
+    pub fn max_lines(mut self, n: usize) -> RevLines<R, D> {
+        self.raw = self.raw.max_lines(n);
+        self
+    }
+
+    /// Fail when a single line is longer than `limit` bytes.
+    /// See [`RawRevLines::max_line_len`].
+    pub fn max_line_len(mut self, limit: usize) -> RevLines<R, D> {
+        self.raw = self.raw.max_line_len(limit);
+        self
+    }
+
+    /// Collect the count and length distribution of the remaining lines, consuming them.
+    /// See [`RawRevLines::stats`].
+    pub fn stats(&mut self) -> io::Result<LineStats> {
+        self.raw.stats()
+    }
+
+    /// See [`RawRevLines::reverse_lines_count_bytes`].
+    pub fn reverse_lines_count_bytes(&mut self, window_bytes: u64) -> io::Result<(usize, u64)> {
+        self.raw.reverse_lines_count_bytes(window_bytes)
+    }
+
+    /// Decode lines with `decoder` instead of the default [`Utf8Decoder`].
+    pub fn with_decoder<E: LineDecoder>(self, decoder: E) -> RevLines<R, E> {
+        RevLines {
+            raw: self.raw,
+            decoder,
+            validate_boundaries: self.validate_boundaries,
+            io_error_policy: self.io_error_policy,
+        }
+    }
+
+    /// Pick the encoding of every line from the bytes of the first line read, which is
+    /// the last line of the reader, e.g. for log formats which end in a trailer naming
+    /// their encoding.
+    ///
+    /// `f` is called once, before the first line is decoded, and lines are decoded as
+    /// UTF-8 when it returns `None`. The first line is decoded with the picked encoding
+    /// as well. Since the first line decoded is examined, the trailer has to be read
+    /// before any other line, which is the case when iterating from the end of the reader.
+    ///
+    /// ```
+    /// use std::io::Cursor;
+    ///
+    /// use rev_lines::{Encoding, RevLines};
+    ///
+    /// let log = Cursor::new(b"caf\xe9\n#latin-1\n".to_vec());
+    /// let lines: Vec<String> = RevLines::new(log)
+    ///     .with_encoding_sniffer(|trailer| (trailer == b"#latin-1").then_some(Encoding::Latin1))
+    ///     .map(Result::unwrap)
+    ///     .collect();
+    ///
+    /// assert_eq!(lines, vec!["#latin-1", "café"]);
+    /// ```
+    pub fn with_encoding_sniffer<F>(self, f: F) -> RevLines<R, EncodingSniffer<F>>
+    where
+        F: Fn(&[u8]) -> Option<Encoding>,
+    {
+        self.with_decoder(EncodingSniffer {
+            sniffer: f,
+            encoding: OnceCell::new(),
+        })
+    }
+
+    /// Returns the number of bytes read from the reader at a time.
+    pub fn capacity(&self) -> usize {
+        self.raw.capacity()
+    }
+
+    /// Start iteration over from the end of the reader. See [`RawRevLines::rewind`].
+    pub fn rewind(&mut self) -> io::Result<()> {
+        self.raw.rewind()
+    }
+
+    /// Unwraps this `RevLines`, returning the underlying reader.
+    /// See [`RawRevLines::into_inner`].
+    pub fn into_inner(self) -> R {
+        self.raw.into_inner()
+    }
+
+    /// Set which line terminators split lines. Defaults to [`LineEnding::Lf`].
+    pub fn line_ending(mut self, line_ending: LineEnding) -> RevLines<R, D> {
+        self.raw = self.raw.line_ending(line_ending);
+        self
+    }
+
+    /// See [`RawRevLines::unicode_line_breaks`].
+    pub fn unicode_line_breaks(mut self, enable: bool) -> RevLines<R, D> {
+        self.raw = self.raw.unicode_line_breaks(enable);
+        self
+    }
+
+    /// Treat a bare `\r` as the very last byte of the reader as the terminator of the last line.
+    /// See [`RawRevLines::trim_trailing_cr`].
+    pub fn trim_trailing_cr(mut self, trim: bool) -> RevLines<R, D> {
+        self.raw = self.raw.trim_trailing_cr(trim);
+        self
+    }
+
+    /// Join lines ending in a backslash with the line after them.
+    /// See [`RawRevLines::join_continuations`].
+    pub fn join_continuations(mut self, join: bool) -> RevLines<R, D> {
+        self.raw = self.raw.join_continuations(join);
+        self
+    }
+
+    /// Fail with [`RevLinesError::Io`] of kind `InvalidData` when the reader does not end
+    /// in a line terminator. See [`RawRevLines::require_trailing_newline`].
+    pub fn require_trailing_newline(mut self, require: bool) -> RevLines<R, D> {
+        self.raw = self.raw.require_trailing_newline(require);
+        self
+    }
+
+    /// See [`RawRevLines::assume_trailing_newline`].
+    pub fn assume_trailing_newline(mut self, assume: bool) -> RevLines<R, D> {
+        self.raw = self.raw.assume_trailing_newline(assume);
+        self
+    }
+
+    /// Set what happens to `\r` bytes in lines. Defaults to [`CrHandling::StripBeforeLf`].
+    pub fn cr_handling(mut self, handling: CrHandling) -> RevLines<R, D> {
+        self.raw = self.raw.cr_handling(handling);
+        self
+    }
+
+    /// Set how line terminators at the end of the reader are handled.
+    /// Defaults to [`TrailingNewlinePolicy::TrimOne`].
+    pub fn trailing_newline_policy(mut self, policy: TrailingNewlinePolicy) -> RevLines<R, D> {
+        self.raw = self.raw.trailing_newline_policy(policy);
+        self
+    }
+
+    /// See [`RawRevLines::keep_terminators`].
+    pub fn keep_terminators(mut self, keep: bool) -> RevLines<R, D> {
+        self.raw = self.raw.keep_terminators(keep);
+        self
+    }
+
+    /// See [`RawRevLines::trim_trailing_empty`].
+    pub fn trim_trailing_empty(mut self, n: usize) -> RevLines<R, D> {
+        self.raw = self.raw.trim_trailing_empty(n);
+        self
+    }
+
+    /// Pair each line with its byte range `[start, end)` in the reader, which excludes
+    /// the line terminator, e.g. to jump to a line in a forward view of the file.
+    ///
+    /// ```
+    /// use std::io::Cursor;
+    ///
+    /// use rev_lines::RevLines;
+    ///
+    /// let file = Cursor::new("first\nsecond\n");
+    /// let mut lines = RevLines::new(file).lines_with_ranges();
+    ///
+    /// assert_eq!(lines.next().unwrap().unwrap(), (6..12, "second".to_string()));
+    /// assert_eq!(lines.next().unwrap().unwrap(), (0..5, "first".to_string()));
+    /// ```
+    pub fn lines_with_ranges(
+        mut self,
+    ) -> impl Iterator<Item = Result<(Range<u64>, String), RevLinesError>> {
+        std::iter::from_fn(move || {
+            let (start, line) = match self.raw.next_line_at().transpose()? {
+                Ok(line) => line,
+                Err(error) => return Some(Err(RevLinesError::Io(error))),
+            };
+            let end = self.raw.last_end;
+
+            Some(self.decode(start, line).map(|line| (start..end, line)))
+        })
+    }
+
+    /// Pair each line with the offset a forward reader would resume from to read it again.
+    ///
+    /// The offset is the absolute position of the first byte of the line, just after the
+    /// terminator of the line before it. Seeking there and reading forward yields this line
+    /// and every line after it, i.e. all lines yielded so far, so it serves as a checkpoint
+    /// when shipping a tail of a log.
+    pub fn lines_with_resume_offset(
+        mut self,
+    ) -> impl Iterator<Item = Result<(String, u64), RevLinesError>> {
+        std::iter::from_fn(move || {
+            let (offset, line) = match self.raw.next_line_at().transpose()? {
+                Ok(line) => line,
+                Err(error) => return Some(Err(RevLinesError::Io(error))),
+            };
+
+            Some(self.decode(offset, line).map(|line| (line, offset)))
+        })
+    }
+
+    /// Yield each line as an `Arc<str>`, which is cheap to clone when lines are handed
+    /// out to several threads.
+    ///
+    /// This does not save any allocations: every line is still decoded into a `String`
+    /// and then copied into an allocation of its own.
+    pub fn shared(self) -> impl Iterator<Item = Result<Arc<str>, RevLinesError>> {
+        self.map(|line| line.map(Arc::from))
+    }
+
+    /// Reverse the characters of each line as well, so the whole content of the reader is
+    /// yielded back to front.
+    ///
+    /// Lines are reversed by `char`, not by grapheme cluster, so combining marks end up in
+    /// front of the character they belonged to.
+    ///
+    /// ```
+    /// use std::io::Cursor;
+    ///
+    /// use rev_lines::RevLines;
+    ///
+    /// let mut rev_lines = RevLines::new(Cursor::new("abc\ndef\n")).fully_reversed();
+    ///
+    /// assert_eq!(rev_lines.next().unwrap().unwrap(), "fed");
+    /// assert_eq!(rev_lines.next().unwrap().unwrap(), "cba");
+    /// ```
+    pub fn fully_reversed(self) -> impl Iterator<Item = Result<String, RevLinesError>> {
+        self.map(|line| line.map(|line| line.chars().rev().collect()))
+    }
+
+    /// Follow the lines with a final [`BofItem::TopReached`] once the start of the reader
+    /// is reached, e.g. to show a "top of file" indicator. An empty reader yields only
+    /// the marker.
+    ///
+    /// The marker is left out when iteration stops before reaching the start of the
+    /// reader, such as after [`RevLines::max_lines`].
+    pub fn with_bof_marker(mut self) -> impl Iterator<Item = BofItem> {
+        let mut done = false;
+
+        std::iter::from_fn(move || {
+            if done {
+                return None;
+            }
+
+            match self.next() {
+                Some(line) => Some(BofItem::Line(line)),
+                None => {
+                    done = true;
+                    self.raw.at_start().then_some(BofItem::TopReached)
+                }
+            }
+        })
+    }
+
+    /// Group the lines into records separated by blank lines, such as stack traces in a
+    /// log. Records are yielded last first, each with its lines in forward order.
+    ///
+    /// Lines holding only whitespace count as blank. Runs of blank lines, and blank
+    /// lines at the start or end of the reader, never yield empty records.
+    ///
+    /// ```
+    /// use std::io::Cursor;
+    ///
+    /// use rev_lines::RevLines;
+    ///
+    /// let mut records = RevLines::new(Cursor::new("a1\na2\n\nb1\nb2\n")).records();
+    ///
+    /// assert_eq!(records.next().unwrap().unwrap(), vec!["b1", "b2"]);
+    /// assert_eq!(records.next().unwrap().unwrap(), vec!["a1", "a2"]);
+    /// assert!(records.next().is_none());
+    /// ```
+    pub fn records(mut self) -> impl Iterator<Item = Result<Vec<String>, RevLinesError>> {
+        std::iter::from_fn(move || {
+            let mut record = Vec::new();
+
+            loop {
+                match self.next() {
+                    Some(Ok(line)) if line.trim().is_empty() => {
+                        if !record.is_empty() {
+                            break;
+                        }
+                    }
+                    Some(Ok(line)) => record.push(line),
+                    Some(Err(error)) => return Some(Err(error)),
+                    None if record.is_empty() => return None,
+                    None => break,
+                }
+            }
+
+            record.reverse();
+            Some(Ok(record))
+        })
+    }
+
+    /// Yield each distinct line only the first time it is read, which is its newest
+    /// occurrence, e.g. for a "most recent unique entries" view. Unlike collapsing
+    /// consecutive duplicates, repeats are skipped wherever they appear.
+    ///
+    /// Every distinct line read is kept in a `HashSet`, so memory grows with the number
+    /// and length of distinct lines. Errors are passed through and not remembered.
+    ///
+    /// ```
+    /// use std::io::Cursor;
+    ///
+    /// use rev_lines::RevLines;
+    ///
+    /// let lines: Vec<String> = RevLines::new(Cursor::new("a\nb\na\nc\na\n"))
+    ///     .distinct()
+    ///     .map(Result::unwrap)
+    ///     .collect();
+    ///
+    /// assert_eq!(lines, vec!["a", "c", "b"]);
+    /// ```
+    pub fn distinct(self) -> impl Iterator<Item = Result<String, RevLinesError>> {
+        let mut seen = HashSet::new();
+
+        self.filter(move |line| match line {
+            Ok(line) => seen.insert(line.clone()),
+            Err(_) => true,
+        })
+    }
+
+    /// Returns the iterator itself, to spell out at the call site that lines are yielded
+    /// last line first, moving towards the start of the reader.
+    ///
+    /// ```
+    /// use std::io::Cursor;
+    ///
+    /// use rev_lines::RevLines;
+    ///
+    /// let rev_lines = RevLines::new(Cursor::new("first\nsecond\nthird\n"));
+    /// let lines: Vec<String> = rev_lines.lines_rev().map(Result::unwrap).collect();
+    ///
+    /// assert_eq!(lines, vec!["third", "second", "first"]);
+    /// ```
+    pub fn lines_rev(self) -> RevLines<R, D> {
+        self
+    }
+
+    /// Collect up to `n` lines, last line first, stopping at the first error.
+    ///
+    /// Nothing past the `n`-th line is read from the reader.
+    pub fn try_collect_n(self, n: usize) -> Result<Vec<String>, RevLinesError> {
+        self.take(n).collect()
+    }
+
+    /// Read `n` lines spread evenly over the last `window_bytes` bytes of the reader, last
+    /// line first, e.g. to get an overview of a large log rather than its last few lines.
+    ///
+    /// The window is split into `n` even parts and the first line starting at or after
+    /// the beginning of each part is read, so offsets snap forward to the next line
+    /// boundary. Fewer than `n` lines are returned when several parts snap to the same
+    /// line or past the end of the reader. Iteration is not affected.
+    pub fn sample_tail(
+        &mut self,
+        n: usize,
+        window_bytes: u64,
+    ) -> Result<Vec<String>, RevLinesError> {
+        let mut lines = Vec::with_capacity(n);
+        let result = self.sample_lines(n, window_bytes, &mut lines);
+        self.raw.restore_reader_position()?;
+
+        result.map(|()| lines)
+    }
+
+    fn sample_lines(
+        &mut self,
+        n: usize,
+        window_bytes: u64,
+        lines: &mut Vec<String>,
+    ) -> Result<(), RevLinesError> {
+        let end = self.raw.reader_len()?;
+        let window = min(window_bytes, end);
+        let mut line = Vec::new();
+        let mut previous_start = None;
+
+        for part in (0..n).rev() {
+            let target = end - window + (window as u128 * part as u128 / n as u128) as u64;
+            let start = self.raw.line_start_from(target, end)?;
+            if start >= end || previous_start == Some(start) {
+                continue;
+            }
+            previous_start = Some(start);
+
+            self.raw.read_line_forward(start, end, &mut line)?;
+            lines.push(self.decode(start, std::mem::take(&mut line))?);
+        }
+
+        Ok(())
+    }
+
+    /// Push every remaining line to the front of `deque`, so the lines end up in their
+    /// original, forward order in front of anything the deque already held.
+    ///
+    /// Stops at the first error, keeping the lines pushed until then.
+    ///
+    /// ```
+    /// use std::collections::VecDeque;
+    /// use std::io::Cursor;
+    ///
+    /// use rev_lines::RevLines;
+    ///
+    /// let mut deque = VecDeque::new();
+    /// RevLines::new(Cursor::new("a\nb\nc\n")).drain_into_front(&mut deque).unwrap();
+    ///
+    /// assert_eq!(deque, ["a", "b", "c"]);
+    /// ```
+    pub fn drain_into_front(self, deque: &mut VecDeque<String>) -> Result<(), RevLinesError> {
+        for line in self {
+            deque.push_front(line?);
+        }
+
+        Ok(())
+    }
+
+    /// Wrap the iterator to look at the next line without consuming it.
+    pub fn peekable_rev(self) -> PeekableRevLines<R, D> {
+        PeekableRevLines {
+            rev_lines: self,
+            peeked: None,
+        }
+    }
+
+    /// Merge the lines of two readers, yielding the larger line of the two streams
+    /// according to `cmp` each time. Lines which compare equal are taken from `self` first.
+    ///
+    /// Both readers must be sorted in ascending order on their own, so that reading
+    /// them in reverse yields descending lines; the merged stream is then descending too.
+    /// Errors are yielded as soon as either stream hits them.
+    ///
+    /// ```
+    /// use std::io::Cursor;
+    ///
+    /// use rev_lines::RevLines;
+    ///
+    /// let a = RevLines::new(Cursor::new("1\n4\n5\n"));
+    /// let b = RevLines::new(Cursor::new("2\n3\n6\n"));
+    ///
+    /// let merged: Vec<String> = a.merge_reversed(b, |a, b| a.cmp(b)).map(Result::unwrap).collect();
+    /// assert_eq!(merged, vec!["6", "5", "4", "3", "2", "1"]);
+    /// ```
+    pub fn merge_reversed<S, E, F>(
+        self,
+        other: RevLines<S, E>,
+        cmp: F,
+    ) -> MergeReversed<R, S, D, E, F>
+    where
+        S: Read + Seek,
+        E: LineDecoder,
+        F: FnMut(&str, &str) -> Ordering,
+    {
+        MergeReversed {
+            left: self.peekable_rev(),
+            right: other.peekable_rev(),
+            cmp,
+        }
+    }
+
+    /// Tag each line with its forward, 1-based line number, so the last line of the
+    /// reader has the highest number.
+    ///
+    /// The total number of lines is needed up front, so this first scans the whole
+    /// reader with [`RawRevLines::count_lines`]. That prepass costs one extra read of
+    /// the reader. Iteration then starts over from the end of the reader.
+    ///
+    /// The prepass ignores [`RevLines::max_lines`], so lines keep their numbers in the
+    /// whole reader when only the last few are read.
+    pub fn lines_indexed(
+        mut self,
+    ) -> Result<impl Iterator<Item = (usize, Result<String, RevLinesError>)>, RevLinesError> {
+        // Numbers count every line, not only those left within the budget of `max_lines`
+        self.raw.reset();
+        let total = self.raw.without_max_lines(RawRevLines::count_lines)?;
+        self.raw.reset();
+
+        Ok(self
+            .enumerate()
+            .map(move |(index, line)| (total - index, line)))
+    }
+
+    /// Pair each line with its forward, 1-based line number like [`RevLines::lines_indexed`],
+    /// but build the numbers lazily from an index of line offsets.
+    ///
+    /// The first call to `next` scans the whole reader once and keeps the offset of every
+    /// line, 8 bytes per line, without copying or decoding any of them. Each line is then
+    /// numbered by looking up its offset. Compared to [`RevLines::lines_indexed`], which
+    /// only keeps a count, this uses memory proportional to the number of lines, but the
+    /// prepass is deferred until the first line is needed and the numbers follow the
+    /// offsets of the lines rather than the order they are yielded in. Like there, the
+    /// prepass ignores [`RevLines::max_lines`].
+    pub fn lines_numbered(self) -> NumberedRevLines<R, D> {
+        NumberedRevLines {
+            rev_lines: self,
+            offsets: None,
+            failed: false,
+        }
+    }
+
+    /// Count the lines up front so the iterator knows its exact length and implements
+    /// [`ExactSizeIterator`], e.g. to preallocate a `Vec` or show "line i of N".
+    ///
+    /// The count is an O(n) prepass over the whole reader with [`RawRevLines::count_lines`],
+    /// after which iteration starts over from the end of the reader.
+    pub fn with_exact_len(mut self) -> Result<ExactRevLines<R, D>, RevLinesError> {
+        self.raw.reset();
+        let remaining = self.raw.count_lines()?;
+        self.raw.reset();
+
+        Ok(ExactRevLines {
+            rev_lines: self,
+            remaining,
+        })
+    }
+
+    /// When a line fails to decode, re-scan the bytes around its line feeds and report
+    /// `RevLinesError::SplitSequence` if one of them falls inside a malformed multibyte sequence.
+    ///
+    /// For valid UTF-8 the line boundaries are always safe: `\n` and `\r` are ASCII and never
+    /// occur as part of a multibyte sequence. This mode only adds certainty for corrupt input.
+    pub fn validate_boundaries(mut self, validate: bool) -> RevLines<R, D> {
+        self.validate_boundaries = validate;
+        self
+    }
+
+    fn decode(&mut self, offset: u64, line: Vec<u8>) -> Result<String, RevLinesError> {
+        let error = match self.decoder.decode_owned(line) {
+            Err(RevLinesError::InvalidUtf8 { source, .. }) => source,
+            result => return result,
+        };
+
+        if self.validate_boundaries {
+            let line_len = error.as_bytes().len() as u64;
+
+            if offset > 0 && self.raw.line_feed_splits_sequence(offset - 1)? {
+                return Err(RevLinesError::SplitSequence { offset: offset - 1 });
+            }
+            if self.raw.line_feed_splits_sequence(offset + line_len)? {
+                return Err(RevLinesError::SplitSequence {
+                    offset: offset + line_len,
+                });
+            }
+        }
+
+        Err(RevLinesError::InvalidUtf8 {
+            offset,
+            source: error,
+        })
+    }
+}
+
+impl<R> From<RawRevLines<R>> for RevLines<R> {
+    fn from(raw: RawRevLines<R>) -> RevLines<R> {
+        RevLines {
+            raw,
+            decoder: Utf8Decoder,
+            validate_boundaries: false,
+            io_error_policy: IoErrorPolicy::Abort,
+        }
+    }
+}
+
+impl<R: Read + Seek, D: LineDecoder> Iterator for RevLines<R, D> {
+    type Item = Result<String, RevLinesError>;
+
+    fn next(&mut self) -> Option<Result<String, RevLinesError>> {
+        let (offset, line) = loop {
+            match self.raw.next_line_at().transpose()? {
+                Ok(line) => break line,
+                Err(error) if self.io_error_policy.recovers_from(&error) => {
+                    if let Err(error) = self.raw.skip_failed_line() {
+                        return Some(Err(RevLinesError::Io(error)));
+                    }
+                }
+                Err(error) => return Some(Err(RevLinesError::Io(error))),
+            }
+        };
+
+        Some(self.decode(offset, line))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.raw.size_hint()
+    }
+}
+
+/// See the implementation for [`RawRevLines`]
+impl<R: Read + Seek, D: LineDecoder> DoubleEndedIterator for RevLines<R, D> {
+    fn next_back(&mut self) -> Option<Result<String, RevLinesError>> {
+        let (offset, line) = match self.raw.next_line_back_at().transpose()? {
+            Ok(line) => line,
+            Err(error) => return Some(Err(RevLinesError::Io(error))),
+        };
+
+        Some(self.decode(offset, line))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::alloc::{GlobalAlloc, Layout, System};
+    use std::cell::{Cell, RefCell};
+    use std::collections::VecDeque;
+    use std::io::{BufReader, Cursor, Read, Seek};
+    use std::num::NonZeroUsize;
+    use std::rc::Rc;
+    use std::sync::{Arc, Mutex};
+
+    use crate::{
+        BofItem, CrHandling, Encoding, IoErrorPolicy, LineDecoder, LineEnding, LineStats,
+        RawRevLines, ReadSeek, RevLines, RevLinesChain, RevLinesError, TailWindow,
+        TrailingNewlinePolicy, Utf8LossyDecoder,
+    };
+
+    type TestResult = Result<(), Box<dyn std::error::Error>>;
+
+    thread_local! {
+        static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+    }
+
+    /// Counts allocations made by the current thread, so tests running in parallel don't interfere
+    struct CountingAllocator;
+
+    unsafe impl GlobalAlloc for CountingAllocator {
+        unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+            let _ = ALLOCATIONS.try_with(|count| count.set(count.get() + 1));
+            System.alloc(layout)
+        }
+
+        unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+            System.dealloc(ptr, layout)
+        }
+
+        unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+            let _ = ALLOCATIONS.try_with(|count| count.set(count.get() + 1));
+            System.realloc(ptr, layout, new_size)
+        }
+    }
+
+    #[global_allocator]
+    static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+    fn allocations() -> usize {
+        ALLOCATIONS.with(|count| count.get())
+    }
+
+    #[test]
+    fn raw_handles_empty_files() -> TestResult {
+        let file = Cursor::new(Vec::new());
+        let mut rev_lines = RawRevLines::new(file);
+
+        assert!(rev_lines.next().transpose()?.is_none());
+
+        Ok(())
+    }
+
+    #[test]
+    fn raw_handles_file_with_one_line() -> TestResult {
+        let text = b"ABCD\n".to_vec();
+        for cap in 1..(text.len() + 1) {
+            let file = Cursor::new(&text);
+            let mut rev_lines = RawRevLines::with_capacity(cap, file);
+
+            assert_eq!(rev_lines.next().transpose()?, Some(b"ABCD".to_vec()));
+            assert_eq!(rev_lines.next().transpose()?, None);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn raw_handles_file_with_multi_lines() -> TestResult {
+        let text = b"ABCDEF\nGHIJK\nLMNOPQRST\nUVWXYZ\n".to_vec();
+        for cap in 5..(text.len() + 1) {
+            let file = Cursor::new(b"ABCDEF\nGHIJK\nLMNOPQRST\nUVWXYZ\n".to_vec());
+            let mut rev_lines = RawRevLines::with_capacity(cap, file);
+
+            assert_eq!(rev_lines.next().transpose()?, Some(b"UVWXYZ".to_vec()));
+            assert_eq!(rev_lines.next().transpose()?, Some(b"LMNOPQRST".to_vec()));
+            assert_eq!(rev_lines.next().transpose()?, Some(b"GHIJK".to_vec()));
+            assert_eq!(rev_lines.next().transpose()?, Some(b"ABCDEF".to_vec()));
+            assert_eq!(rev_lines.next().transpose()?, None);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn raw_handles_windows_file_with_multi_lines() -> TestResult {
+        let text = b"ABCDEF\r\nGHIJK\r\nLMNOP\rQRST\r\nUVWXYZ\r\n".to_vec();
+        for cap in 1..(text.len() + 1) {
+            let file = Cursor::new(&text);
+            let mut rev_lines = RawRevLines::with_capacity(cap, file);
+
+            assert_eq!(rev_lines.next().transpose()?, Some(b"UVWXYZ".to_vec()));
+            assert_eq!(rev_lines.next().transpose()?, Some(b"LMNOP\rQRST".to_vec())); // bare CR not stripped
+            assert_eq!(rev_lines.next().transpose()?, Some(b"GHIJK".to_vec()));
+            assert_eq!(rev_lines.next().transpose()?, Some(b"ABCDEF".to_vec()));
+            assert_eq!(rev_lines.next().transpose()?, None);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn raw_handles_read_line_without_allocating() -> TestResult {
+        let text = b"ABCDEF\nGHIJK\r\nLMNOPQRST\nUVWXYZ\n".to_vec();
+        let mut rev_lines = RawRevLines::with_buffers(
+            Vec::with_capacity(4),
+            Vec::with_capacity(16),
+            Cursor::new(&text),
+        );
+
+        assert_eq!(rev_lines.capacity(), 4);
+        // The first read seeks to the end of the reader and fills the buffer
+        assert_eq!(rev_lines.read_line()?, Some(&b"UVWXYZ"[..]));
+
+        let before = allocations();
+
+        assert_eq!(rev_lines.read_line()?, Some(&b"LMNOPQRST"[..]));
+        assert_eq!(rev_lines.read_line()?, Some(&b"GHIJK"[..]));
+        assert_eq!(rev_lines.read_line()?, Some(&b"ABCDEF"[..]));
+        assert_eq!(rev_lines.read_line()?, None);
+
+        assert_eq!(allocations(), before);
+
+        Ok(())
+    }
+
+    #[test]
+    fn raw_handles_read_line_into() -> TestResult {
+        let text = b"ABCDEF\nGHIJK\nLMNOPQRST\nUVWXYZ".to_vec();
+        for cap in 1..(text.len() + 1) {
+            let mut rev_lines = RawRevLines::with_capacity(cap, Cursor::new(&text));
+            let mut buf = b"leftovers".to_vec();
+
+            assert!(rev_lines.read_line_into(&mut buf)?);
+            assert_eq!(buf, b"UVWXYZ");
+            assert!(rev_lines.read_line_into(&mut buf)?);
+            assert_eq!(buf, b"LMNOPQRST");
+            assert!(rev_lines.read_line_into(&mut buf)?);
+            assert_eq!(buf, b"GHIJK");
+            assert!(rev_lines.read_line_into(&mut buf)?);
+            assert_eq!(buf, b"ABCDEF");
+            assert!(!rev_lines.read_line_into(&mut buf)?);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn raw_handles_cursor_reaching_start_of_file() -> TestResult {
+        // Lengths which are and are not multiples of the capacities below
+        for text in [&b"ABCD\nEFGH\n"[..], b"ABC\nDEFGH\nIJ", b"A\nB\nC\nD\n"] {
+            for cap in 1..(text.len() + 2) {
+                let mut rev_lines = RawRevLines::with_capacity(cap, Cursor::new(text));
+                let mut lines = Vec::new();
+
+                for line in rev_lines.by_ref() {
+                    lines.push(line?);
+                }
+
+                let mut expected: Vec<_> = text
+                    .split(|byte| *byte == b'\n')
+                    .map(|line| line.to_vec())
+                    .collect();
+                if text.ends_with(b"\n") {
+                    expected.pop();
+                }
+                expected.reverse();
+
+                assert_eq!(lines, expected, "capacity {cap}");
+
+                // Calling next past the end must not move the cursor any further
+                for _ in 0..3 {
+                    assert_eq!(rev_lines.next().transpose()?, None);
+                    assert_eq!(rev_lines.reader_cursor, 0);
+                    assert_eq!(rev_lines.read_len, 0);
+                    assert_eq!(rev_lines.buffer_end, 0);
+                    assert_eq!(rev_lines.reader.stream_position()?, 0);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn raw_handles_small_buf_reader_over_large_file() -> TestResult {
+        let mut text = Vec::new();
+        for i in 1..2000 {
+            text.extend(std::iter::repeat_n(b'a' + (i % 26) as u8, i % 97));
+            text.push(b'\n');
+        }
+
+        let mut expected: Vec<_> = text.split(|byte| *byte == b'\n').collect();
+        expected.pop();
+        expected.reverse();
+
+        for buf_reader_cap in [1, 7, 64, 4096] {
+            for cap in [1, 13, 100, 4096] {
+                let reader = BufReader::with_capacity(buf_reader_cap, Cursor::new(&text));
+                let rev_lines = RawRevLines::with_capacity(cap, reader);
+                let lines = rev_lines.collect::<Result<Vec<_>, _>>()?;
+
+                assert_eq!(
+                    lines, expected,
+                    "capacity {cap}, BufReader {buf_reader_cap}"
+                );
+            }
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_handles_exact_len() -> TestResult {
+        let file = Cursor::new("ABCD\n\nEF\r\nGH\nIJ".to_string());
+        let mut rev_lines = RevLines::with_capacity(3, file);
+        rev_lines.next().transpose()?;
+
+        // Iteration starts over from the end of the reader
+        let mut exact = rev_lines.with_exact_len()?;
+        assert_eq!(exact.len(), 5);
+
+        exact.next().transpose()?;
+        assert_eq!(exact.len(), 4);
+
+        let rest = exact.collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(rest, vec!["GH", "EF", "", "ABCD"]);
+
+        let file = Cursor::new(Vec::new());
+        let exact = RevLines::new(file).with_exact_len()?;
+        assert_eq!(exact.len(), 0);
+        assert_eq!(exact.count(), 0);
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_handles_stats() -> TestResult {
+        let text = "a\nbbbb\r\n\nccccccccc\ndd\n";
+
+        for cap in 1..(text.len() + 1) {
+            let mut rev_lines = RevLines::with_capacity(cap, Cursor::new(text));
+            let stats = rev_lines.stats()?;
+
+            let expected = LineStats {
+                count: 5,
+                min_len: 0,
+                max_len: 9,
+                total_bytes: 16,
+            };
+            assert_eq!(stats, expected, "capacity {cap}");
+            assert_eq!(stats.mean_len(), Some(3.2));
+            assert_eq!(rev_lines.next().transpose()?, None);
+        }
+
+        let stats = RevLines::new(Cursor::new("")).stats()?;
+        assert_eq!(stats, LineStats::default());
+        assert_eq!(stats.mean_len(), None);
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_handles_failed_buffer_allocation() -> TestResult {
+        let file = Cursor::new("ABCD\nEF\n");
+        assert!(RevLines::try_with_capacity(usize::MAX, file).is_err());
+
+        let file = Cursor::new("ABCD\nEF\n");
+        let rev_lines = RevLines::try_with_capacity(3, file)?;
+        assert_eq!(rev_lines.capacity(), 3);
+        assert_eq!(
+            rev_lines.collect::<Result<Vec<_>, _>>()?,
+            vec!["EF", "ABCD"]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_handles_lines_with_ranges() -> TestResult {
+        let text = "ABCD\n\nEF\r\nGH\nIJ";
+
+        for cap in 1..(text.len() + 1) {
+            let file = Cursor::new(text);
+            let lines = RevLines::with_capacity(cap, file)
+                .lines_with_ranges()
+                .collect::<Result<Vec<_>, _>>()?;
+
+            assert_eq!(lines.len(), 5);
+            for (range, line) in lines {
+                let range = range.start as usize..range.end as usize;
+                assert_eq!(&text[range], line, "capacity {cap}");
+            }
+        }
+
+        // Joined lines span every line they were joined from
+        let text = "x\na\\\nb\\\r\nbb\n";
+        for cap in 1..(text.len() + 1) {
+            let lines = RevLines::with_capacity(cap, Cursor::new(text))
+                .join_continuations(true)
+                .lines_with_ranges()
+                .collect::<Result<Vec<_>, _>>()?;
+
+            assert_eq!(lines[0], (2..11, "abbb".to_string()), "capacity {cap}");
+            for (range, line) in lines {
+                let range = range.start as usize..range.end as usize;
+                let joined = text[range].replace("\\\r\n", "").replace("\\\n", "");
+                assert_eq!(joined, line, "capacity {cap}");
+            }
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_handles_strings() -> TestResult {
+        let text = "ABCD\n\nEF\r\nGH\nIJ\n";
+
+        let lines = RevLines::from_string(text).collect::<Vec<&str>>();
+        assert_eq!(lines, vec!["IJ", "GH", "EF", "", "ABCD"]);
+
+        let lines = RevLines::from_owned_string(text.to_string()).collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(lines, vec!["IJ", "GH", "EF", "", "ABCD"]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_handles_lines_with_resume_offset() -> TestResult {
+        let text = "ABCD\n\nEF\r\nGH\nIJ\n";
+        let lines = RevLines::with_capacity(3, Cursor::new(text))
+            .lines_with_resume_offset()
+            .collect::<Result<Vec<_>, _>>()?;
+
+        for (index, (line, offset)) in lines.iter().enumerate() {
+            let mut file = Cursor::new(text);
+            file.seek(std::io::SeekFrom::Start(*offset))?;
+            let mut rest = String::new();
+            std::io::Read::read_to_string(&mut file, &mut rest)?;
+
+            let expected = lines[..=index]
+                .iter()
+                .rev()
+                .map(|(line, _)| line.as_str())
+                .collect::<Vec<_>>();
+            assert_eq!(
+                rest.lines().collect::<Vec<_>>(),
+                expected,
+                "resuming before {line:?}"
+            );
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_handles_shared_lines() -> TestResult {
+        let text = "ABCD\n\nEF\r\nGH\nIJ\n";
+        let lines = RevLines::new(Cursor::new(text)).collect::<Result<Vec<_>, _>>()?;
+        let shared = RevLines::new(Cursor::new(text))
+            .shared()
+            .collect::<Result<Vec<_>, _>>()?;
+
+        assert_eq!(shared.len(), lines.len());
+        for (shared, line) in shared.iter().zip(&lines) {
+            assert_eq!(&**shared, line);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_handles_cursors_without_buffering() -> TestResult {
+        let text = b"ABCD\n\nEF\r\nGH\nIJ\n".to_vec();
+
+        for cap in 1..(text.len() + 1) {
+            let lines = RevLines::from_cursor_with_capacity(cap, Cursor::new(&text))
+                .collect::<Result<Vec<_>, _>>()?;
+            assert_eq!(lines, vec!["IJ", "GH", "EF", "", "ABCD"], "capacity {cap}");
+        }
+
+        let lines = RevLines::from_cursor(Cursor::new(text)).collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(lines, vec!["IJ", "GH", "EF", "", "ABCD"]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_reports_the_offset_of_invalid_utf8() -> TestResult {
+        let file = Cursor::new(b"ABCD\nE\xffF\nGH\n".to_vec());
+        let mut rev_lines = RevLines::with_capacity(2, file);
+        assert_eq!(rev_lines.next().transpose()?, Some("GH".to_string()));
+
+        match rev_lines.next() {
+            Some(Err(error @ RevLinesError::InvalidUtf8 { offset: 5, .. })) => {
+                assert_eq!(
+                    error.to_string(),
+                    "invalid UTF-8 in line starting at byte offset 5"
+                );
+            }
+            other => panic!("expected invalid UTF-8 at offset 5, got {other:?}"),
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_handles_lossy_lines() -> TestResult {
+        let file = Cursor::new(b"ABCD\nE\xffF\nGH\n".to_vec());
+        let lines = RevLines::lossy(file).collect::<std::io::Result<Vec<_>>>()?;
+        assert_eq!(lines, ["GH", "E\u{FFFD}F", "ABCD"]);
+
+        let file = OverstatedReader {
+            inner: Cursor::new(b"ABCD\n".to_vec()),
+            extra: -10,
+        };
+        let mut lines = RevLines::lossy(file);
+        assert!(lines.next().is_some_and(|line| line.is_err()));
+
+        let file = Cursor::new(b"ABCD\nE\xffF\nGH\n".to_vec());
+        let lines = RevLines::with_capacity(2, file)
+            .into_lossy()
+            .collect::<std::io::Result<Vec<_>>>()?;
+        assert_eq!(lines, ["GH", "E\u{FFFD}F", "ABCD"]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_formats_errors() {
+        use std::error::Error;
+
+        // Transparent, so both the message and the source are those of the `io::Error`
+        let inner = String::from_utf8(vec![0xff]).unwrap_err();
+        let error = RevLinesError::from(std::io::Error::other(inner));
+        assert_eq!(
+            error.to_string(),
+            "invalid utf-8 sequence of 1 bytes from index 0"
+        );
+        assert!(error.source().is_none());
+
+        let error = RevLinesError::from(String::from_utf8(vec![0xff]).unwrap_err());
+        assert!(matches!(
+            error,
+            RevLinesError::InvalidUtf8 { offset: 0, .. }
+        ));
+        assert!(error.source().unwrap().is::<std::string::FromUtf8Error>());
+
+        let error = RevLinesError::SplitSequence { offset: 7 };
+        assert_eq!(
+            error.to_string(),
+            "line feed at byte offset 7 splits a malformed UTF-8 sequence"
+        );
+        assert!(error.source().is_none());
+    }
+
+    #[test]
+    fn it_handles_bof_marker() -> TestResult {
+        // An empty reader yields the marker alone
+        for text in ["", "A", "ABCD\n\nEF\r\nGH\nIJ\n"] {
+            let items = RevLines::with_capacity(2, Cursor::new(text))
+                .with_bof_marker()
+                .collect::<Vec<_>>();
+            let lines = items.iter().filter(|item| matches!(item, BofItem::Line(_)));
+
+            assert_eq!(lines.count(), text.lines().count());
+            assert_eq!(items.len(), text.lines().count() + 1);
+            assert!(matches!(items.last(), Some(BofItem::TopReached)));
+        }
+
+        let file = Cursor::new("ABCD\nEF\n");
+        let mut items = RevLines::new(file).max_lines(1).with_bof_marker();
+        assert!(items.all(|item| matches!(item, BofItem::Line(Ok(_)))));
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_handles_fold_bytes() -> TestResult {
+        let file = Cursor::new("ABCD\n\nEF\r\nGH\nIJK\n");
+        let mut rev_lines = RevLines::with_capacity(3, file);
+
+        let tail_len = rev_lines.fold_bytes(0, 3, |len, line| len + line.len())?;
+        assert_eq!(tail_len, "EFGHIJK".len());
+        assert_eq!(rev_lines.next().transpose()?, Some(String::new()));
+
+        let rest = rev_lines.fold_bytes(Vec::new(), 10, |mut lines, line| {
+            lines.push(line.to_vec());
+            lines
+        })?;
+        assert_eq!(rest, vec![b"ABCD".to_vec()]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_handles_normalized_endings() -> TestResult {
+        let text = "a\rb\r\r\nc\r\n";
+
+        let lines = RevLines::new(Cursor::new(text)).collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(lines, vec!["c", "a\rb\r"]);
+
+        let lines = RevLines::new(Cursor::new(text))
+            .normalize_endings(true)
+            .collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(lines, vec!["c", "a\rb"]);
+
+        let lines = RevLines::new(Cursor::new(text))
+            .normalize_endings(true)
+            .convert_lone_cr(true)
+            .collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(lines, vec!["c", "a\nb"]);
+
+        // Lone `\r` are only converted while normalizing
+        let lines = RevLines::new(Cursor::new(text))
+            .convert_lone_cr(true)
+            .collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(lines, vec!["c", "a\rb\r"]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn raw_handles_null_runs() -> TestResult {
+        // The zeros of a hole in a sparse file read like any other content
+        let mut text = b"a\n".to_vec();
+        text.extend(std::iter::repeat_n(0, 10_000));
+        text.extend(b"\nb\0\0c\n");
+
+        let lines = RawRevLines::new(Cursor::new(&text)).collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(
+            lines,
+            vec![b"b\0\0c".to_vec(), vec![0; 10_000], b"a".to_vec()]
+        );
+
+        let lines = RawRevLines::new(Cursor::new(&text))
+            .skip_null_runs(true)
+            .collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(lines, vec![b"b\0c".to_vec(), vec![0], b"a".to_vec()]);
+
+        Ok(())
+    }
+
+    /// Counts the reads made on the wrapped reader
+    struct CountingReader<R> {
+        inner: R,
+        reads: Rc<Cell<usize>>,
+    }
+
+    impl<R: Read> Read for CountingReader<R> {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            self.reads.set(self.reads.get() + 1);
+            self.inner.read(buf)
+        }
+    }
+
+    impl<R: Seek> Seek for CountingReader<R> {
+        fn seek(&mut self, pos: std::io::SeekFrom) -> std::io::Result<u64> {
+            self.inner.seek(pos)
+        }
+    }
+
+    /// Truncates its content to `shrink_to` bytes before its third read
+    struct ShrinkingReader {
+        inner: Cursor<Vec<u8>>,
+        shrink_to: usize,
+        reads: usize,
+    }
+
+    impl Read for ShrinkingReader {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            self.reads += 1;
+            if self.reads == 3 {
+                self.inner.get_mut().truncate(self.shrink_to);
+            }
+            self.inner.read(buf)
+        }
+    }
+
+    impl Seek for ShrinkingReader {
+        fn seek(&mut self, pos: std::io::SeekFrom) -> std::io::Result<u64> {
+            self.inner.seek(pos)
+        }
+    }
+
+    #[test]
+    fn raw_handles_shrinking_readers() -> TestResult {
+        let file = ShrinkingReader {
+            inner: Cursor::new(b"ABCD\nEFGH\nIJKL\n".to_vec()),
+            shrink_to: 5,
+            reads: 0,
+        };
+        let mut rev_lines = RawRevLines::from_parts(
+            Vec::with_capacity(4),
+            Vec::new(),
+            BufReader::with_capacity(0, file),
+        );
+        assert_eq!(rev_lines.next().transpose()?, Some(b"IJKL".to_vec()));
+
+        let error = rev_lines.next().unwrap().unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::UnexpectedEof);
+        assert_eq!(
+            error.to_string(),
+            "reverse cursor underflow: the reader ended at byte 5, but bytes up to 15 were read before"
+        );
+
+        Ok(())
+    }
+
+    /// Records the position of every read
+    struct RecordingReader<R> {
+        inner: Cursor<R>,
+        starts: Rc<RefCell<Vec<u64>>>,
+    }
+
+    impl<R: AsRef<[u8]>> Read for RecordingReader<R> {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            self.starts.borrow_mut().push(self.inner.position());
+            self.inner.read(buf)
+        }
+    }
+
+    impl<R: AsRef<[u8]>> Seek for RecordingReader<R> {
+        fn seek(&mut self, pos: std::io::SeekFrom) -> std::io::Result<u64> {
+            self.inner.seek(pos)
+        }
+    }
+
+    #[test]
+    fn raw_handles_aligned_reads() -> TestResult {
+        let text = (0..100).map(|i| format!("line {i}\n")).collect::<String>();
+        let expected = RawRevLines::new(Cursor::new(&text)).collect::<Result<Vec<_>, _>>()?;
+
+        for (cap, alignment) in [(128, 64), (100, 64), (7, 4), (3, 4)] {
+            let starts = Rc::new(RefCell::new(Vec::new()));
+            let file = RecordingReader {
+                inner: Cursor::new(&text),
+                starts: starts.clone(),
+            };
+            let lines = RawRevLines::with_capacity(cap, file)
+                .align_reads(alignment)
+                .collect::<Result<Vec<_>, _>>()?;
+            assert_eq!(lines, expected, "capacity {cap} aligned to {alignment}");
+
+            let aligned = starts.borrow().iter().all(|start| start % alignment == 0);
+            assert_eq!(aligned, cap >= alignment as usize, "capacity {cap}");
+        }
+
+        Ok(())
+    }
+
+    /// Fails a single read with `TimedOut`, after reading part of the requested bytes
+    struct FlakyReader<R> {
+        inner: R,
+        reads: usize,
+        fail_at: usize,
+    }
+
+    impl<R: Read> Read for FlakyReader<R> {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            self.reads += 1;
+            if self.reads == self.fail_at {
+                let len = buf.len() / 2;
+                self.inner.read_exact(&mut buf[..len])?;
+                return Err(std::io::ErrorKind::TimedOut.into());
+            }
+            self.inner.read(buf)
+        }
+    }
+
+    impl<R: Seek> Seek for FlakyReader<R> {
+        fn seek(&mut self, pos: std::io::SeekFrom) -> std::io::Result<u64> {
+            self.inner.seek(pos)
+        }
+    }
+
+    /// Reports a length `extra` bytes longer than the bytes it can actually deliver
+    struct OverstatedReader<R> {
+        inner: R,
+        extra: i64,
+    }
+
+    impl<R: Read> Read for OverstatedReader<R> {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            self.inner.read(buf)
+        }
+    }
+
+    impl<R: Seek> Seek for OverstatedReader<R> {
+        fn seek(&mut self, pos: std::io::SeekFrom) -> std::io::Result<u64> {
+            match pos {
+                std::io::SeekFrom::End(n) => {
+                    self.inner.seek(std::io::SeekFrom::End(n + self.extra))
+                }
+                pos => self.inner.seek(pos),
+            }
+        }
+    }
+
+    #[test]
+    fn raw_handles_short_reads() -> TestResult {
+        let text = "ABCD\n\nEF\r\nGH\nIJ\n";
+        let expected = RawRevLines::new(Cursor::new(text)).collect::<Result<Vec<_>, _>>()?;
+
+        for extra in [1, 5, 40] {
+            for cap in 1..(text.len() + 1) {
+                let file = OverstatedReader {
+                    inner: Cursor::new(text),
+                    extra,
+                };
+                let lines = RawRevLines::with_capacity(cap, file).collect::<Result<Vec<_>, _>>()?;
+
+                assert_eq!(lines, expected, "{extra} extra bytes at capacity {cap}");
+            }
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_handles_io_error_policy() -> TestResult {
+        let text = (0..3000).map(|i| format!("line {i}\n")).collect::<String>();
+        let expected = text.lines().rev().collect::<Vec<_>>();
+
+        let flaky = |fail_at| FlakyReader {
+            inner: Cursor::new(text.clone()),
+            reads: 0,
+            fail_at,
+        };
+
+        let mut rev_lines = RevLines::new(flaky(2));
+        let error = rev_lines.find_map(Result::err);
+        assert!(
+            matches!(error, Some(RevLinesError::Io(error)) if error.kind() == std::io::ErrorKind::TimedOut)
+        );
+
+        for fail_at in 1..5 {
+            let lines = RevLines::new(flaky(fail_at))
+                .io_error_policy(IoErrorPolicy::SkipLine)
+                .collect::<Result<Vec<_>, _>>()?;
+
+            // At most the line which was being read when the error happened is missing
+            assert!(expected.len() - lines.len() <= 1, "failing read {fail_at}");
+            let mut expected = expected.iter();
+            assert!(lines
+                .iter()
+                .all(|line| expected.any(|expected| expected == line)));
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_handles_peeking() -> TestResult {
+        let file = Cursor::new(b"AB\nC\xffD\nEF\n".to_vec());
+        let mut rev_lines = RevLines::new(file).peekable_rev();
+
+        assert!(matches!(rev_lines.peek(), Some(Ok(line)) if line == "EF"));
+        assert!(matches!(rev_lines.peek(), Some(Ok(line)) if line == "EF"));
+        assert_eq!(rev_lines.next().transpose()?, Some("EF".to_string()));
+
+        assert!(matches!(
+            rev_lines.peek(),
+            Some(Err(RevLinesError::InvalidUtf8 { offset: 3, .. }))
+        ));
+        assert!(matches!(
+            rev_lines.next(),
+            Some(Err(RevLinesError::InvalidUtf8 { offset: 3, .. }))
+        ));
+
+        assert_eq!(rev_lines.next().transpose()?, Some("AB".to_string()));
+        assert!(rev_lines.peek().is_none());
+        assert!(rev_lines.next().is_none());
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_handles_merging_sorted_readers() -> TestResult {
+        let left = RevLines::with_capacity(3, Cursor::new("1\n3\n4\n10\n12\n"));
+        let right = RevLines::with_capacity(5, Cursor::new("2\n4\n11\n20\n21"));
+        let by_number = |a: &str, b: &str| a.parse::<u32>().unwrap().cmp(&b.parse().unwrap());
+
+        let merged = left
+            .merge_reversed(right, by_number)
+            .collect::<Result<Vec<String>, RevLinesError>>()?;
+        assert_eq!(
+            merged,
+            vec!["21", "20", "12", "11", "10", "4", "4", "3", "2", "1"]
+        );
+
+        let left = RevLines::new(Cursor::new(b"1\n\xff\n5\n".to_vec()));
+        let right = RevLines::new(Cursor::new(""));
+        let mut merged = left.merge_reversed(right, by_number);
+        assert_eq!(merged.next().transpose()?, Some("5".to_string()));
+        assert!(matches!(
+            merged.next(),
+            Some(Err(RevLinesError::InvalidUtf8 { offset: 2, .. }))
+        ));
+        assert_eq!(merged.next().transpose()?, Some("1".to_string()));
+        assert!(merged.next().is_none());
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_handles_records() -> TestResult {
+        let text = "\n\nError: one\n  at a\n  at b\n\n \n\nError: two\n  at c\n\n";
+
+        for cap in 1..(text.len() + 1) {
+            let records = RevLines::with_capacity(cap, Cursor::new(text))
+                .records()
+                .collect::<Result<Vec<_>, RevLinesError>>()?;
+            assert_eq!(
+                records,
+                vec![
+                    vec!["Error: two", "  at c"],
+                    vec!["Error: one", "  at a", "  at b"],
+                ]
+            );
+        }
+
+        let mut records = RevLines::new(Cursor::new("")).records();
+        assert!(records.next().is_none());
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_handles_refreshing_tail_window() -> TestResult {
+        let mut window = TailWindow::new(Cursor::new(b"A\n".to_vec()), 3);
+        assert_eq!(window.refresh()?, ["A"]);
+
+        window
+            .get_mut()
+            .get_mut()
+            .extend_from_slice(b"BB\nC\r\nDDDD\n");
+        assert_eq!(window.refresh()?, ["BB", "C", "DDDD"]);
+        assert_eq!(window.refresh()?, ["BB", "C", "DDDD"]);
+
+        window.get_mut().get_mut().extend_from_slice(b"E");
+        assert_eq!(window.refresh()?, ["C", "DDDD", "E"]);
+
+        window.get_mut().get_mut().extend_from_slice(b"\xff\n");
+        assert!(matches!(
+            window.refresh(),
+            Err(RevLinesError::InvalidUtf8 { offset: 13, .. })
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_handles_max_lines() -> TestResult {
+        let reads = Rc::new(Cell::new(0));
+        let file = CountingReader {
+            inner: Cursor::new("ABCD\n\nEF\r\nGH\nIJ\n"),
+            reads: reads.clone(),
+        };
+        let mut rev_lines = RevLines::with_capacity(1, file).max_lines(2);
+        assert_eq!(rev_lines.size_hint(), (0, Some(2)));
+
+        assert_eq!(rev_lines.next().transpose()?, Some("IJ".to_string()));
+        assert_eq!(rev_lines.next().transpose()?, Some("GH".to_string()));
+        assert_eq!(rev_lines.size_hint(), (0, Some(0)));
+
+        let reads_before = reads.get();
+        assert_eq!(rev_lines.next().transpose()?, None);
+        assert_eq!(reads.get(), reads_before);
+
+        let file = Cursor::new("ABCD\n\nEF\r\nGH\nIJ\n");
+        let exact = RevLines::new(file).max_lines(3).with_exact_len()?;
+        assert_eq!(exact.len(), 3);
+        assert_eq!(
+            exact.collect::<Result<Vec<_>, _>>()?,
+            vec!["IJ", "GH", "EF"]
+        );
+
+        let mut rev_lines = RevLines::new(Cursor::new("ABCD\n\nEF\r\nGH\nIJ\n"));
+        rev_lines.by_ref().take(3).for_each(drop);
+        let mut rev_lines = rev_lines.max_lines(1);
+        assert_eq!(rev_lines.next().transpose()?, None);
+
+        Ok(())
+    }
+
+    #[test]
+    fn raw_handles_rewind_one_line() -> TestResult {
+        let file = Cursor::new("ABCD\n\nEF\r\nGH\nIJ\n");
+        let mut rev_lines = RawRevLines::with_capacity(3, file);
+
+        assert_eq!(rev_lines.next().transpose()?, Some(b"IJ".to_vec()));
+        let line = rev_lines.next().transpose()?.unwrap_or_default();
+        assert_eq!(line, b"GH");
+
+        rev_lines.rewind_one_line(line);
+        assert_eq!(rev_lines.next().transpose()?, Some(b"GH".to_vec()));
+        assert_eq!(rev_lines.next().transpose()?, Some(b"EF".to_vec()));
+
+        Ok(())
+    }
+
+    #[test]
+    fn raw_handles_buffer_snapshot() -> TestResult {
+        let file = Cursor::new("ABCD\nEF\nGH\n");
+        let mut rev_lines = RawRevLines::with_capacity(4, file);
+
+        assert_eq!(rev_lines.next().transpose()?, Some(b"GH".to_vec()));
+        // The line feed in front of "GH" is left in the buffer until the next line is read
+        assert_eq!(rev_lines.buffer_snapshot(), (&b"\n"[..], 1, 3));
+
+        Ok(())
+    }
+
+    #[test]
+    fn raw_handles_max_refills_per_line() -> TestResult {
+        let mut text = b"AB\nCD\n".to_vec();
+        text.extend(std::iter::repeat_n(b'x', 100));
+
+        let mut rev_lines =
+            RawRevLines::with_capacity(4, Cursor::new(&text)).max_refills_per_line(10);
+        let error = rev_lines.next().and_then(Result::err);
+        assert_eq!(
+            error.map(|error| error.kind()),
+            Some(std::io::ErrorKind::InvalidData)
+        );
+
+        text.extend(b"\nEF\n");
+        let mut rev_lines =
+            RawRevLines::with_capacity(4, Cursor::new(&text)).max_refills_per_line(1);
+        assert_eq!(rev_lines.next().transpose()?, Some(b"EF".to_vec()));
+
+        Ok(())
+    }
+
+    /// Records the furthest position any read reached
+    struct FurthestReader<R> {
+        inner: Cursor<R>,
+        furthest: Rc<Cell<u64>>,
+    }
+
+    impl<R: AsRef<[u8]>> Read for FurthestReader<R> {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            let read = self.inner.read(buf)?;
+            self.furthest
+                .set(self.furthest.get().max(self.inner.position()));
+            Ok(read)
+        }
+    }
+
+    impl<R: AsRef<[u8]>> Seek for FurthestReader<R> {
+        fn seek(&mut self, pos: std::io::SeekFrom) -> std::io::Result<u64> {
+            self.inner.seek(pos)
+        }
+    }
+
+    #[test]
+    fn raw_handles_start_offset() -> TestResult {
+        let text = (0..1000).map(|i| format!("line {i}\n")).collect::<String>();
+        let offset = text.find("line 500").unwrap() as u64;
+
+        for (offset, first, rest) in [(offset, "line 499", 498), (offset + 6, "line 5", 499)] {
+            let furthest = Rc::new(Cell::new(0));
+            let file = FurthestReader {
+                inner: Cursor::new(&text),
+                furthest: furthest.clone(),
+            };
+            let mut rev_lines = RawRevLines::with_start_offset(offset, file);
+
+            assert_eq!(
+                rev_lines.next().transpose()?,
+                Some(first.as_bytes().to_vec())
+            );
+            assert_eq!(rev_lines.next_back().transpose()?, Some(b"line 0".to_vec()));
+            assert_eq!(rev_lines.count(), rest);
+            assert!(furthest.get() <= offset, "read up to {}", furthest.get());
+        }
+
+        let lines = RevLines::with_start_offset(100, Cursor::new("AB\nCD\n"))
+            .collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(lines, ["CD", "AB"]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn raw_handles_rewind() -> TestResult {
+        let text = "A\nBB\r\n\nCCC\n";
+
+        for cap in 1..(text.len() + 1) {
+            let mut rev_lines = RawRevLines::with_capacity(cap, Cursor::new(text));
+            let first = rev_lines.by_ref().collect::<Result<Vec<_>, _>>()?;
+
+            rev_lines.rewind()?;
+            let second = rev_lines.by_ref().collect::<Result<Vec<_>, _>>()?;
+            assert_eq!(first, second, "capacity {cap}");
+
+            rev_lines.next();
+            rev_lines.next_back();
+            rev_lines.rewind()?;
+            let third = rev_lines.collect::<Result<Vec<_>, _>>()?;
+            assert_eq!(first, third, "capacity {cap}");
+        }
+
+        let mut rev_lines = RevLines::new(Cursor::new(text)).max_lines(1);
+        assert_eq!(rev_lines.next().transpose()?, Some("CCC".to_string()));
+        rev_lines.rewind()?;
+        assert_eq!(rev_lines.next().transpose()?, Some("CCC".to_string()));
+
+        Ok(())
+    }
+
+    #[test]
+    fn raw_handles_max_line_len() -> TestResult {
+        let mut text = b"AB\nCD\n".to_vec();
+        text.extend(std::iter::repeat_n(b'x', 10 * 1024));
+        text.extend(b"\nEF\n");
+
+        for cap in [7, 1024, 4096, 16 * 1024] {
+            let mut rev_lines =
+                RawRevLines::with_capacity(cap, Cursor::new(&text)).max_line_len(1024);
+            assert_eq!(rev_lines.next().transpose()?, Some(b"EF".to_vec()));
+            let error = rev_lines.next().and_then(Result::err);
+            assert_eq!(
+                error.map(|error| error.kind()),
+                Some(std::io::ErrorKind::InvalidData),
+                "capacity {cap}"
+            );
+
+            // The rest of the long line fails as well, until the lines in front of it
+            let lines = rev_lines.filter_map(Result::ok).collect::<Vec<_>>();
+            assert!(
+                lines.ends_with(&[b"CD".to_vec(), b"AB".to_vec()]),
+                "capacity {cap}"
+            );
+            assert!(
+                lines.iter().all(|line| line.len() <= 1024),
+                "capacity {cap}"
+            );
+        }
+
+        let mut rev_lines = RevLines::new(Cursor::new(&text)).max_line_len(10 * 1024);
+        assert_eq!(
+            rev_lines.nth(1).transpose()?.map(|line| line.len()),
+            Some(10 * 1024)
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn raw_handles_lines_with_terminators() -> TestResult {
+        let fixtures: [(LineEnding, &[u8]); 5] = [
+            (LineEnding::Lf, b"ABCD\n\nEF\r\nGH\r\r\nIJ\n"),
+            (LineEnding::Lf, b"ABCD\n\nEF\r\nGH\nIJ"),
+            (LineEnding::CrLf, b"AB\nCD\r\n\r\nEF\r\n"),
+            (LineEnding::CrLf, b"AB\r\nCD\r"),
+            (LineEnding::Auto, b"AB\rCD\r\nEF\n\rGH\r"),
+        ];
+
+        for (line_ending, text) in fixtures {
+            for cap in 1..(text.len() + 1) {
+                let pairs = RawRevLines::with_capacity(cap, Cursor::new(text))
+                    .line_ending(line_ending)
+                    .lines_with_terminators()
+                    .collect::<Result<Vec<_>, _>>()?;
+                let rebuilt = pairs
+                    .into_iter()
+                    .rev()
+                    .flat_map(|(line, terminator)| [line, terminator])
+                    .flatten()
+                    .collect::<Vec<_>>();
+
+                assert_eq!(rebuilt, text, "{line_ending:?} with capacity {cap}");
+            }
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn raw_handles_drain_to_end() -> TestResult {
+        let file = Cursor::new("ABCD\n\nEF\r\nGH\nIJ\n");
+        let mut rev_lines = RawRevLines::with_capacity(3, file);
+
+        assert_eq!(rev_lines.next().transpose()?, Some(b"IJ".to_vec()));
+        assert_eq!(rev_lines.next().transpose()?, Some(b"GH".to_vec()));
+        assert_eq!(
+            rev_lines.drain_to_end()?,
+            vec![b"EF".to_vec(), vec![], b"ABCD".to_vec()]
+        );
+
+        assert_eq!(rev_lines.next().transpose()?, None);
+        assert!(rev_lines.drain_to_end()?.is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn raw_handles_find_last_matching() -> TestResult {
+        let log = "INFO boot\nERROR disk full\nINFO retry\nERROR disk still full\nINFO ok\n";
+
+        for cap in 1..(log.len() + 1) {
+            let mut rev_lines = RawRevLines::with_capacity(cap, Cursor::new(log));
+
+            let found = rev_lines.find_last_matching(b"ERROR")?;
+            assert_eq!(found, Some((37, b"ERROR disk still full".to_vec())));
+
+            // Only the lines after the match were read
+            assert_eq!(rev_lines.next().transpose()?, Some(b"INFO retry".to_vec()));
+            assert_eq!(rev_lines.find_last_matching(b"WARN")?, None);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn raw_handles_expected_lines_hint() -> TestResult {
+        let text = (0..1000)
+            .map(|i| format!("line {i:04}\n"))
+            .collect::<String>();
+
+        let mut rev_lines = RawRevLines::with_capacity(64, Cursor::new(&text));
+        let hint = rev_lines.expected_lines_hint()?;
+        assert!((500..2000).contains(&hint), "hint was {hint}");
+
+        // Iteration carries on unaffected, before and after it started
+        assert_eq!(rev_lines.next().transpose()?, Some(b"line 0999".to_vec()));
+        assert_eq!(rev_lines.expected_lines_hint()?, hint);
+        assert_eq!(rev_lines.next().transpose()?, Some(b"line 0998".to_vec()));
+        assert_eq!(rev_lines.count(), 998);
+
+        assert_eq!(RawRevLines::new(Cursor::new("")).expected_lines_hint()?, 0);
+        assert_eq!(
+            RawRevLines::new(Cursor::new("A\nB\n")).expected_lines_hint()?,
+            2
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn raw_handles_contains_line() -> TestResult {
+        let log = "INFO boot\nERROR disk full\nINFO retry\nERROR disk still full\nINFO ok\n";
+
+        for cap in 1..(log.len() + 1) {
+            let rev_lines = || RawRevLines::with_capacity(cap, Cursor::new(log));
+
+            assert!(rev_lines().contains_line(b"INFO retry", 3)?);
+            assert!(!rev_lines().contains_line(b"INFO retry", 2)?);
+            assert!(!rev_lines().contains_line(b"ERROR disk", 5)?);
+            assert!(!rev_lines().contains_line(b"INFO boot", 4)?);
+            assert!(rev_lines().contains_line(b"INFO boot", 5)?);
+
+            // Reading stopped right after the match
+            let mut rev_lines = rev_lines();
+            assert!(rev_lines.contains_line(b"ERROR disk still full", 5)?);
+            assert_eq!(rev_lines.next().transpose()?, Some(b"INFO retry".to_vec()));
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_handles_str_split_policies() -> TestResult {
+        let cases: [(TrailingNewlinePolicy, &[&str]); 2] = [
+            (TrailingNewlinePolicy::TrimOne, &["b", "a"]),
+            (TrailingNewlinePolicy::Keep, &["", "b", "a"]),
+        ];
+
+        for (policy, expected) in cases {
+            let lines = RevLines::new(Cursor::new("a\nb\n"))
+                .trailing_newline_policy(policy)
+                .collect::<Result<Vec<_>, _>>()?;
+            assert_eq!(lines, expected, "{policy:?}");
+
+            let mut forward = match policy {
+                TrailingNewlinePolicy::Keep => "a\nb\n".split('\n').collect::<Vec<_>>(),
+                _ => "a\nb\n".split_terminator('\n').collect::<Vec<_>>(),
+            };
+            forward.reverse();
+            assert_eq!(lines, forward, "{policy:?}");
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_handles_unicode_line_breaks() -> TestResult {
+        let text = "café\u{2028}naïve\u{2029}\u{2028}résumé\nend\u{2028}";
+
+        for cap in 1..(text.len() + 1) {
+            let lines = RevLines::with_capacity(cap, Cursor::new(text))
+                .unicode_line_breaks(true)
+                .collect::<Result<Vec<_>, _>>()?;
+            assert_eq!(
+                lines,
+                ["end", "résumé", "", "naïve", "café"],
+                "capacity {cap}"
+            );
+
+            let lines =
+                RevLines::with_capacity(cap, Cursor::new(text)).collect::<Result<Vec<_>, _>>()?;
+            assert_eq!(
+                lines,
+                ["end\u{2028}", "café\u{2028}naïve\u{2029}\u{2028}résumé"],
+                "capacity {cap}"
+            );
+        }
+
+        let lines = RevLines::new(Cursor::new("\u{2028}a"))
+            .unicode_line_breaks(true)
+            .collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(lines, ["a", ""]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_handles_assume_trailing_newline() -> TestResult {
+        let policies = [
+            TrailingNewlinePolicy::TrimOne,
+            TrailingNewlinePolicy::Keep,
+            TrailingNewlinePolicy::TrimAll,
+        ];
+
+        for policy in policies {
+            for cap in 1..5 {
+                let read = |text: &'static str| {
+                    RevLines::with_capacity(cap, Cursor::new(text))
+                        .trailing_newline_policy(policy)
+                        .assume_trailing_newline(true)
+                        .require_trailing_newline(true)
+                        .collect::<Result<Vec<_>, _>>()
+                };
+
+                assert_eq!(
+                    read("a\nb")?,
+                    read("a\nb\n")?,
+                    "{policy:?} with capacity {cap}"
+                );
+                assert_eq!(
+                    read("a\n\nb")?,
+                    read("a\n\nb\n")?,
+                    "{policy:?} with capacity {cap}"
+                );
+                assert!(read("")?.is_empty());
+            }
+        }
+
+        let lines = RevLines::new(Cursor::new("a\nb"))
+            .trailing_newline_policy(TrailingNewlinePolicy::Keep)
+            .assume_trailing_newline(true)
+            .collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(lines, ["", "b", "a"]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn raw_handles_size_hint() -> TestResult {
+        let texts = ["A\nBB\r\n\nCCC\n", "\n\n\n", "A", "\r\n", ""];
+        let policies = [
+            TrailingNewlinePolicy::TrimOne,
+            TrailingNewlinePolicy::Keep,
+            TrailingNewlinePolicy::TrimAll,
+        ];
+
+        for text in texts {
+            for policy in policies {
+                for cap in 1..(text.len() + 2) {
+                    let mut rev_lines = RawRevLines::with_capacity(cap, Cursor::new(text))
+                        .trailing_newline_policy(policy);
+                    assert_eq!(rev_lines.size_hint(), (0, None));
+
+                    let mut remaining = RawRevLines::with_capacity(cap, Cursor::new(text))
+                        .trailing_newline_policy(policy)
+                        .count();
+                    while rev_lines.next().transpose()?.is_some() {
+                        remaining -= 1;
+                        let (_, upper) = rev_lines.size_hint();
+                        let max = text.len() + usize::from(policy == TrailingNewlinePolicy::Keep);
+                        assert!(
+                            upper.is_some_and(|upper| remaining <= upper && upper <= max),
+                            "{text:?} with capacity {cap}: {remaining} lines left, hint {upper:?}"
+                        );
+                    }
+                    assert_eq!(remaining, 0);
+                }
+            }
+        }
+
+        let mut rev_lines = RawRevLines::new(Cursor::new("A\nB\nC\n")).max_lines(1);
+        rev_lines.next();
+        assert_eq!(rev_lines.size_hint(), (0, Some(0)));
+
+        // The limit is lower than the number of lines read already
+        let mut rev_lines = RawRevLines::new(Cursor::new("A\nB\nC\n"));
+        rev_lines.next();
+        rev_lines.next();
+        let rev_lines = rev_lines.max_lines(1);
+        assert_eq!(rev_lines.size_hint(), (0, Some(0)));
+
+        Ok(())
+    }
+
+    #[test]
+    fn raw_handles_size_hint_of_only_newlines() -> TestResult {
+        let policies = [
+            TrailingNewlinePolicy::TrimOne,
+            TrailingNewlinePolicy::Keep,
+            TrailingNewlinePolicy::TrimAll,
+        ];
+
+        for text in ["\n", "\n\n", "\n\n\n"] {
+            for policy in policies {
+                let mut rev_lines =
+                    RawRevLines::new(Cursor::new(text)).trailing_newline_policy(policy);
+                rev_lines.fill_to_capacity()?;
+
+                let (_, upper) = rev_lines.size_hint();
+                let count = rev_lines.count();
+                assert!(
+                    upper.is_some_and(|upper| upper >= count),
+                    "{text:?} with {policy:?}: {count} lines, hint {upper:?}"
+                );
+            }
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_handles_open() -> TestResult {
+        let readme = std::fs::read_to_string("README.md")?;
+        let mut expected = readme.lines().map(String::from).collect::<Vec<_>>();
+        expected.reverse();
+
+        let lines = RevLines::open("README.md")?.collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(lines, expected);
+
+        let error = RevLines::open("does-not-exist.txt").err().unwrap();
+        assert_eq!(error.kind(), std::io::ErrorKind::NotFound);
+
+        Ok(())
+    }
+
+    #[test]
+    fn raw_handles_lines_with_offsets() -> TestResult {
+        let texts = ["A\nBB\r\n\nCCC\n", "\r\n\r\nA", "AAAA\nB\n\n", ""];
+
+        for text in texts {
+            let mut expected = Vec::new();
+            let mut offset = 0;
+            for line in text.split_inclusive('\n') {
+                let content = line.trim_end_matches('\n').trim_end_matches('\r');
+                expected.push((offset as u64, content.as_bytes().to_vec()));
+                offset += line.len();
+            }
+            expected.reverse();
+
+            for cap in 1..(text.len() + 2) {
+                let lines = RawRevLines::with_capacity(cap, Cursor::new(text))
+                    .lines_with_offsets()
+                    .collect::<Result<Vec<_>, _>>()?;
+                assert_eq!(lines, expected, "{text:?} with capacity {cap}");
+            }
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn raw_handles_double_ended_iteration() -> TestResult {
+        let cases: [(&str, &[&str]); 5] = [
+            ("A\nB\nC\n", &["A", "B", "C"]),
+            ("A\r\nB\r\nC", &["A", "B", "C"]),
+            ("\nA\n\nB\n", &["", "A", "", "B"]),
+            ("A", &["A"]),
+            ("", &[]),
+        ];
+
+        for (text, expected) in cases {
+            let expected = expected
+                .iter()
+                .map(|line| line.as_bytes())
+                .collect::<Vec<_>>();
+
+            for cap in 1..(text.len() + 2) {
+                for front_first in [false, true] {
+                    let mut rev_lines = RawRevLines::with_capacity(cap, Cursor::new(text));
+                    let (mut front, mut back) = (Vec::new(), Vec::new());
+                    let mut from_front = front_first;
+
+                    loop {
+                        let (line, lines) = match from_front {
+                            true => (rev_lines.next_back(), &mut front),
+                            false => (rev_lines.next(), &mut back),
+                        };
+                        let Some(line) = line else {
+                            break;
+                        };
+                        lines.push(line?);
+                        from_front = !from_front;
+                    }
+                    assert!(rev_lines.next().is_none());
+                    assert!(rev_lines.next_back().is_none());
+
+                    back.reverse();
+                    front.append(&mut back);
+                    assert_eq!(front, expected, "{text:?} with capacity {cap}");
+                }
+            }
+        }
+
+        let mut rev_lines = RawRevLines::from_cursor(Cursor::new("A\nB\nC\n"));
+        assert_eq!(rev_lines.next_back().transpose()?, Some(b"A".to_vec()));
+        assert_eq!(rev_lines.next_back().transpose()?, Some(b"B".to_vec()));
+        assert_eq!(rev_lines.next().transpose()?, Some(b"C".to_vec()));
+        assert_eq!(rev_lines.next_back().transpose()?, None);
+
+        let mut rev_lines = RevLines::new(Cursor::new("A\nB\nC\n"));
+        assert_eq!(rev_lines.next().transpose()?, Some("C".to_string()));
+        assert_eq!(rev_lines.next_back().transpose()?, Some("A".to_string()));
+        assert_eq!(rev_lines.next().transpose()?, Some("B".to_string()));
+        assert_eq!(rev_lines.next().transpose()?, None);
+        assert_eq!(rev_lines.next_back().transpose()?, None);
+
+        // Options the front doesn't split lines by are rejected rather than overlapping
+        let text = "A\u{2028}B\nC\n";
+        let rev_lines = [
+            RawRevLines::new(Cursor::new(text)).unicode_line_breaks(true),
+            RawRevLines::new(Cursor::new(text)).keep_terminators(true),
+            RawRevLines::new(Cursor::new(text)).join_continuations(true),
+            RawRevLines::with_fixed_width(2, Cursor::new(text)),
+        ];
+        for mut rev_lines in rev_lines {
+            assert!(rev_lines.next().is_some());
+            let error = rev_lines.next_back().unwrap().unwrap_err();
+            assert_eq!(error.kind(), std::io::ErrorKind::Unsupported);
+        }
+
+        let mut rev_lines = RevLines::new(Cursor::new(text)).unicode_line_breaks(true);
+        assert_eq!(rev_lines.next().transpose()?, Some("C".to_string()));
+        assert!(matches!(
+            rev_lines.next_back(),
+            Some(Err(RevLinesError::Io(error))) if error.kind() == std::io::ErrorKind::Unsupported
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_handles_into_inner() -> TestResult {
+        let text = "first\nsecond\nthird\n";
+
+        let mut raw = RawRevLines::with_capacity(4, Cursor::new(text));
+        assert_eq!(raw.next().transpose()?, Some(b"third".to_vec()));
+        let mut reader = raw.into_inner();
+        reader.seek(std::io::SeekFrom::Start(6))?;
+        let mut rest = String::new();
+        reader.read_to_string(&mut rest)?;
+        assert_eq!(rest, "second\nthird\n");
+
+        let mut rev_lines = RevLines::new(Cursor::new(text));
+        assert_eq!(rev_lines.next().transpose()?, Some("third".to_string()));
+        assert_eq!(rev_lines.into_inner().into_inner(), text);
+
+        Ok(())
+    }
+
+    #[test]
+    fn raw_handles_keep_terminators() -> TestResult {
+        let cases: [(&str, &[&str]); 4] = [
+            ("A\nB\r\n", &["B\r\n", "A\n"]),
+            ("A\nB", &["B", "A\n"]),
+            ("A\n\r\n\nB\n", &["B\n", "\n", "\r\n", "A\n"]),
+            ("\n", &["\n"]),
+        ];
+
+        for (text, expected) in cases {
+            let expected = expected
+                .iter()
+                .map(|line| line.as_bytes())
+                .collect::<Vec<_>>();
+
+            for cap in 1..(text.len() + 1) {
+                let mut lines = RawRevLines::with_capacity(cap, Cursor::new(text))
+                    .keep_terminators(true)
+                    .collect::<Result<Vec<_>, _>>()?;
+                assert_eq!(lines, expected, "{text:?} with capacity {cap}");
+
+                lines.reverse();
+                assert_eq!(lines.concat(), text.as_bytes());
+            }
+        }
+
+        let lines = RevLines::new(Cursor::new("A\nB\r\n"))
+            .keep_terminators(true)
+            .collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(lines, ["B\r\n", "A\n"]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn raw_handles_trim_trailing_empty() -> TestResult {
+        let cases: [(&str, usize, &[&str]); 5] = [
+            ("a\nb\n\n\n", 2, &["b", "a"]),
+            ("a\nb\n\n\n", 1, &["", "b", "a"]),
+            ("a\nb\n\n\n", 0, &["", "", "b", "a"]),
+            ("a\n\nb\n\n", 5, &["b", "", "a"]),
+            ("\n\n\n", 5, &[]),
+        ];
+
+        for (text, n, expected) in cases {
+            let expected = expected
+                .iter()
+                .map(|line| line.as_bytes())
+                .collect::<Vec<_>>();
+
+            for cap in 1..(text.len() + 1) {
+                let mut rev_lines =
+                    RawRevLines::with_capacity(cap, Cursor::new(text)).trim_trailing_empty(n);
+                let lines = rev_lines.by_ref().collect::<Result<Vec<_>, _>>()?;
+                assert_eq!(lines, expected, "{text:?} with n {n} and capacity {cap}");
+
+                rev_lines.reset();
+                assert_eq!(rev_lines.count_lines()?, expected.len());
+            }
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn raw_handles_trailing_newline_policies() -> TestResult {
+        let cases: [(TrailingNewlinePolicy, &str, &[&str]); 6] = [
+            (TrailingNewlinePolicy::TrimOne, "a\n", &["a"]),
+            (TrailingNewlinePolicy::TrimOne, "a\n\n", &["", "a"]),
+            (TrailingNewlinePolicy::Keep, "a\n", &["", "a"]),
+            (TrailingNewlinePolicy::Keep, "a\n\n", &["", "", "a"]),
+            (TrailingNewlinePolicy::TrimAll, "a\n", &["a"]),
+            (TrailingNewlinePolicy::TrimAll, "a\n\n", &["a"]),
+        ];
+
+        for (policy, text, expected) in cases {
+            let expected = expected
+                .iter()
+                .map(|line| line.as_bytes())
+                .collect::<Vec<_>>();
+
+            for cap in 1..(text.len() + 1) {
+                let mut rev_lines = RawRevLines::with_capacity(cap, Cursor::new(text))
+                    .trailing_newline_policy(policy);
+                let lines = rev_lines.by_ref().collect::<Result<Vec<_>, _>>()?;
+                assert_eq!(lines, expected, "{policy:?} with capacity {cap}");
+
+                rev_lines.reset();
+                assert_eq!(rev_lines.count_lines()?, expected.len());
+            }
+        }
+
+        Ok(())
+    }
+
+    struct UppercaseDecoder;
+
+    impl LineDecoder for UppercaseDecoder {
+        fn decode(&self, bytes: &[u8]) -> Result<String, RevLinesError> {
+            Ok(String::from_utf8_lossy(bytes).to_uppercase())
+        }
+    }
+
+    #[test]
+    fn it_handles_boxed_readers() -> TestResult {
+        let file: Box<dyn ReadSeek + Send> = Box::new(Cursor::new("ABCD\n\nEF\r\nGH\nIJ\n"));
+        let lines = RevLines::with_capacity(3, file).collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(lines, vec!["IJ", "GH", "EF", "", "ABCD"]);
+
+        let mut inner = Cursor::new("AB\nCD\n");
+        let file: Box<dyn ReadSeek + '_> = Box::new(&mut inner);
+        let lines = RawRevLines::new(file).collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(lines, vec![b"CD".to_vec(), b"AB".to_vec()]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_handles_borrowed_readers() -> TestResult {
+        let mut file = Cursor::new("AB\nCD\nEF\n");
+
+        let lines = RevLines::with_capacity(2, &mut file).try_collect_n(2)?;
+        assert_eq!(lines, vec!["EF", "CD"]);
+
+        file.seek(std::io::SeekFrom::Start(0))?;
+        let lines = RevLines::new(&mut file).collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(lines, vec!["EF", "CD", "AB"]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_handles_from_current_position() -> TestResult {
+        let text = "ABCD\n\nEF\r\nGH\nIJ\n";
+        let cases = [
+            (0, vec![]),
+            (3, vec!["ABC"]),
+            (5, vec!["ABCD"]),
+            (10, vec!["EF", "", "ABCD"]),
+            (12, vec!["GH", "EF", "", "ABCD"]),
+        ];
+
+        for (position, expected) in cases {
+            let mut file = Cursor::new(text);
+            file.seek(std::io::SeekFrom::Start(position))?;
+
+            // Counting the lines up front starts over from the same end
+            let rev_lines = RevLines::from_current_position(file)?.with_exact_len()?;
+            assert_eq!(rev_lines.len(), expected.len());
+            assert_eq!(rev_lines.collect::<Result<Vec<_>, _>>()?, expected);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_handles_nonzero_capacity() -> TestResult {
+        let cap = NonZeroUsize::new(5).unwrap();
+        let rev_lines = RevLines::with_nonzero_capacity(cap, Cursor::new("ABCD\n\nEF\r\nGH\nIJ\n"));
+        assert_eq!(rev_lines.capacity(), 5);
+
+        let lines = rev_lines.collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(lines, vec!["IJ", "GH", "EF", "", "ABCD"]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_handles_fully_reversed() -> TestResult {
+        let lines = RevLines::with_capacity(2, Cursor::new("abc\ndef\n"))
+            .fully_reversed()
+            .collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(lines, vec!["fed", "cba"]);
+
+        let lines = RevLines::new(Cursor::new("añb\r\n€\n"))
+            .fully_reversed()
+            .collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(lines, vec!["€", "bña"]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_handles_sample_tail() -> TestResult {
+        let text = (0..1000)
+            .map(|i| format!("line {i:04}\n"))
+            .collect::<String>();
+        let mut rev_lines = RevLines::with_capacity(64, Cursor::new(&text));
+        assert_eq!(rev_lines.next().transpose()?, Some("line 0999".to_string()));
+
+        let lines = rev_lines.sample_tail(4, 1000)?;
+        assert_eq!(
+            lines,
+            vec!["line 0975", "line 0950", "line 0925", "line 0900"]
+        );
+
+        // Offsets within a line snap to the next one
+        let lines = rev_lines.sample_tail(3, 1000)?;
+        assert_eq!(lines, vec!["line 0967", "line 0934", "line 0900"]);
+
+        // Parts snapping to the same line or past the end are left out
+        let lines = rev_lines.sample_tail(8, 15)?;
+        assert_eq!(lines, vec!["line 0999"]);
+        assert_eq!(rev_lines.sample_tail(5, 1_000_000)?.len(), 5);
+        assert_eq!(rev_lines.sample_tail(5, 1_000_000)?[4], "line 0000");
+
+        // Iteration carries on unaffected
+        assert_eq!(rev_lines.next().transpose()?, Some("line 0998".to_string()));
+
+        let mut rev_lines = RevLines::new(Cursor::new("a\r\nb\r\nc")).line_ending(LineEnding::CrLf);
+        assert_eq!(rev_lines.sample_tail(3, 100)?, vec!["c", "b", "a"]);
+
+        // Offsets right after a two byte terminator are line starts
+        let text = "aa\r\nbb\r\ncc\r\ndd\r\n";
+        let mut rev_lines = RevLines::new(Cursor::new(text)).line_ending(LineEnding::CrLf);
+        assert_eq!(rev_lines.sample_tail(4, 100)?, vec!["dd", "cc", "bb", "aa"]);
+
+        // Offsets within a terminator or a line with a bare \n snap to the next line
+        let text = "aa\r\nb\nb\r\ncc\r\n";
+        let mut rev_lines = RevLines::new(Cursor::new(text)).line_ending(LineEnding::CrLf);
+        assert_eq!(rev_lines.sample_tail(1, 10)?, vec!["b\nb"]);
+        assert_eq!(rev_lines.sample_tail(1, 9)?, vec!["b\nb"]);
+        assert_eq!(rev_lines.sample_tail(1, 7)?, vec!["cc"]);
+
+        let mut rev_lines =
+            RevLines::new(Cursor::new("aa\rbb\rcc\rdd\r")).line_ending(LineEnding::Cr);
+        assert_eq!(rev_lines.sample_tail(4, 100)?, vec!["dd", "cc", "bb", "aa"]);
+        assert_eq!(rev_lines.sample_tail(2, 8)?, vec!["dd", "cc"]);
+
+        let mut rev_lines = RevLines::new(Cursor::new("a\r\nb\rc\n")).line_ending(LineEnding::Auto);
+        assert_eq!(rev_lines.sample_tail(3, 100)?, vec!["c", "b", "a"]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_handles_drain_into_front() -> TestResult {
+        let text = "ABCD\n\nEF\r\nGH\nIJ\n";
+        let mut deque = VecDeque::from(["KL".to_string()]);
+
+        RevLines::with_capacity(3, Cursor::new(text)).drain_into_front(&mut deque)?;
+        assert_eq!(deque, ["ABCD", "", "EF", "GH", "IJ", "KL"]);
+
+        let mut deque = VecDeque::new();
+        let file = Cursor::new(b"ABCD\nE\xffF\nGH\n".to_vec());
+        let result = RevLines::new(file).drain_into_front(&mut deque);
+        assert!(matches!(
+            result,
+            Err(RevLinesError::InvalidUtf8 { offset: 5, .. })
+        ));
+        assert_eq!(deque, ["GH"]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_handles_distinct_lines() -> TestResult {
+        let lines = RevLines::new(Cursor::new("a\nb\na\nc\na\n"))
+            .distinct()
+            .collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(lines, ["a", "c", "b"]);
+
+        let mut lines = RevLines::new(Cursor::new(b"x\n\xff\nx\n\xff\n".to_vec())).distinct();
+        assert!(lines.next().is_some_and(|line| line.is_err()));
+        assert_eq!(lines.next().transpose()?, Some("x".to_string()));
+        assert!(lines.next().is_some_and(|line| line.is_err()));
+        assert!(lines.next().is_none());
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_handles_encoding_sniffers() -> TestResult {
+        let sniff = |trailer: &[u8]| match trailer {
+            b"# encoding: latin-1" => Some(Encoding::Latin1),
+            _ => None,
+        };
+
+        let log = b"caf\xe9\nna\xefve\n# encoding: latin-1\n".to_vec();
+        let lines = RevLines::new(Cursor::new(&log))
+            .with_encoding_sniffer(sniff)
+            .collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(lines, ["# encoding: latin-1", "naïve", "café"]);
+
+        let log = b"caf\xc3\xa9\nna\xefve\n".to_vec();
+        let mut lines = RevLines::new(Cursor::new(&log)).with_encoding_sniffer(sniff);
+        assert!(matches!(
+            lines.next(),
+            Some(Err(RevLinesError::InvalidUtf8 { offset: 6, .. }))
+        ));
+        assert_eq!(lines.next().transpose()?, Some("café".to_string()));
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_handles_grep() -> TestResult {
+        let log =
+            b"INFO boot\nERROR disk full\nINFO \xff\nERROR disk still full\nINFO ok\n".to_vec();
+
+        let matches = RevLines::grep(Cursor::new(&log), "disk", 2)?;
+        assert_eq!(
+            matches,
+            vec![
+                (33, "ERROR disk still full".to_string()),
+                (10, "ERROR disk full".to_string()),
+            ]
+        );
+
+        assert_eq!(RevLines::grep(Cursor::new(&log), "disk", 1)?.len(), 1);
+        assert!(RevLines::grep(Cursor::new(&log), "WARN", 5)?.is_empty());
+        assert!(matches!(
+            RevLines::grep(Cursor::new(&log), "INFO", 5),
+            Err(RevLinesError::InvalidUtf8 { offset: 26, .. })
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_handles_try_collect_n() -> TestResult {
+        let lines = RevLines::new(Cursor::new("AB\nCD\n")).try_collect_n(5)?;
+        assert_eq!(lines, vec!["CD", "AB"]);
+
+        let file = Cursor::new(b"ABCD\nE\xffF\nGH\n".to_vec());
+        let lines = RevLines::with_capacity(2, file).try_collect_n(1)?;
+        assert_eq!(lines, vec!["GH"]);
+
+        let file = Cursor::new(b"ABCD\nE\xffF\nGH\n".to_vec());
+        assert!(matches!(
+            RevLines::with_capacity(2, file).try_collect_n(3),
+            Err(RevLinesError::InvalidUtf8 { offset: 5, .. })
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_handles_lossy_lines_with_replacement_flag() -> TestResult {
+        let file = Cursor::new(b"ABCD\nE\xffF\n\nGH\xe2\x82\n".to_vec());
+        let lines = RevLines::with_capacity(2, file)
+            .lines_utf8_lossy_bytes()
+            .collect::<Result<Vec<_>, _>>()?;
+
+        assert_eq!(
+            lines,
+            vec![
+                ("GH\u{FFFD}".to_string(), true),
+                (String::new(), false),
+                ("E\u{FFFD}F".to_string(), true),
+                ("ABCD".to_string(), false),
+            ]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_keeps_truncated_sequences_on_their_line() -> TestResult {
+        let text = b"a\xc2\nb\xe2\x82\n\xf0\x9f\x98\nc\n";
+
+        for cap in 1..(text.len() + 1) {
+            let lines = RawRevLines::with_capacity(cap, Cursor::new(text))
+                .collect::<Result<Vec<_>, _>>()?;
+            assert_eq!(
+                lines,
+                vec![
+                    b"c".to_vec(),
+                    b"\xf0\x9f\x98".to_vec(),
+                    b"b\xe2\x82".to_vec(),
+                    b"a\xc2".to_vec(),
+                ]
+            );
+
+            let mut rev_lines = RevLines::with_capacity(cap, Cursor::new(text));
+            assert_eq!(rev_lines.next().transpose()?, Some("c".to_string()));
+            for offset in [7, 3, 0] {
+                match rev_lines.next() {
+                    Some(Err(RevLinesError::InvalidUtf8 { offset: found, .. })) => {
+                        assert_eq!(found, offset, "capacity {cap}");
+                    }
+                    other => panic!("expected invalid UTF-8 at offset {offset}, got {other:?}"),
+                }
+            }
+            assert!(rev_lines.next().is_none());
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_handles_custom_decoders() -> TestResult {
+        let file = Cursor::new(b"abc\nd\xffe\nf\n".to_vec());
+        let lines = RevLines::with_capacity(2, file)
+            .with_decoder(UppercaseDecoder)
+            .collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(lines, vec!["F", "D\u{FFFD}E", "ABC"]);
+
+        let file = Cursor::new(b"abc\nd\xffe\nf\n".to_vec());
+        let lines = RevLines::new(file)
+            .with_decoder(Utf8LossyDecoder)
+            .collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(lines, vec!["f", "d\u{FFFD}e", "abc"]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn raw_handles_set_delimiter() -> TestResult {
+        let text = b"h1\nh2\nb1\0b2\0".to_vec();
+
+        for cap in 1..(text.len() + 1) {
+            let mut rev_lines = RawRevLines::with_capacity(cap, Cursor::new(&text));
+            rev_lines.set_delimiter(b'\0');
+            assert_eq!(rev_lines.next().transpose()?, Some(b"b2".to_vec()));
+
+            rev_lines.set_delimiter(b'\n');
+            let lines = rev_lines.collect::<Result<Vec<_>, _>>()?;
+            assert_eq!(lines, vec![b"b1".to_vec(), b"h2".to_vec(), b"h1".to_vec()]);
+        }
+
+        // A carriage return is only stripped in front of a line feed delimiter
+        let text = b"x\0y\r\nz\r\n".to_vec();
+
+        for cap in 1..(text.len() + 1) {
+            let mut rev_lines = RawRevLines::with_capacity(cap, Cursor::new(&text));
+            assert_eq!(rev_lines.next().transpose()?, Some(b"z".to_vec()));
+
+            rev_lines.set_delimiter(b'\0');
+            let lines = rev_lines.collect::<Result<Vec<_>, _>>()?;
+            assert_eq!(lines, vec![b"y\r".to_vec(), b"x".to_vec()]);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn raw_handles_line_endings() -> TestResult {
+        let text = b"A\r\nB\nC\rD\r\n".to_vec();
+        let cases: [(LineEnding, &[&[u8]]); 5] = [
+            (LineEnding::Lf, &[b"C\rD", b"B", b"A"]),
+            (LineEnding::CrLf, &[b"B\nC\rD", b"A"]),
+            (LineEnding::Cr, &[b"\n", b"D", b"\nB\nC", b"A"]),
+            (LineEnding::Auto, &[b"D", b"C", b"B", b"A"]),
+            (LineEnding::ControlChars, &[b"D", b"C", b"B", b"A"]),
+        ];
+
+        for (line_ending, expected) in cases {
+            for cap in 1..(text.len() + 1) {
+                let file = Cursor::new(&text);
+                let rev_lines = RawRevLines::with_capacity(cap, file).line_ending(line_ending);
+                let lines = rev_lines.collect::<Result<Vec<_>, _>>()?;
+
+                assert_eq!(lines, expected, "{line_ending:?} with capacity {cap}");
+            }
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn raw_handles_control_char_line_endings() -> TestResult {
+        let text = b"A\x1eB\nC\r\nD\x0cE\x1dF\x1e".to_vec();
+
+        for cap in 1..(text.len() + 1) {
+            let lines = RawRevLines::with_capacity(cap, Cursor::new(&text))
+                .line_ending(LineEnding::ControlChars)
+                .collect::<Result<Vec<_>, _>>()?;
+            let expected: [&[u8]; 6] = [b"F", b"E", b"D", b"C", b"B", b"A"];
+
+            assert_eq!(lines, expected, "capacity {cap}");
+        }
+
+        Ok(())
+    }
+
+    /// Split `text` front to back, as a reference for what reading it in reverse yields
+    fn forward_lines(text: &[u8], line_ending: LineEnding) -> Vec<Vec<u8>> {
+        let mut lines = Vec::new();
+        let mut line = Vec::new();
+        let mut bytes = text.iter().copied().peekable();
+
+        while let Some(byte) = bytes.next() {
+            let crlf = byte == b'\r' && bytes.peek() == Some(&b'\n');
+            let ends_line = match line_ending {
+                LineEnding::Lf => byte == b'\n',
+                LineEnding::CrLf => crlf,
+                LineEnding::Cr => byte == b'\r',
+                _ => byte == b'\n' || byte == b'\r',
+            };
+
+            if !ends_line {
+                line.push(byte);
+                continue;
+            }
+            if crlf && line_ending != LineEnding::Cr {
+                bytes.next();
+            }
+            if line_ending == LineEnding::Lf && line.last() == Some(&b'\r') {
+                line.pop();
+            }
+            lines.push(std::mem::take(&mut line));
+        }
+
+        // A trailing terminator ends the last line rather than starting an empty one
+        if !line.is_empty() {
+            lines.push(line);
+        }
+
+        lines.reverse();
+        lines
+    }
+
+    #[test]
+    fn raw_handles_tiny_files() -> TestResult {
+        let mut inputs = vec![Vec::new()];
+        for len in 1..4 {
+            let shorter = inputs
+                .iter()
+                .filter(|input| input.len() == len - 1)
+                .cloned();
+            let longer = shorter
+                .flat_map(|input| [b'a', b'\n', b'\r'].map(|byte| [&input[..], &[byte]].concat()))
+                .collect::<Vec<_>>();
+            inputs.extend(longer);
+        }
+        assert_eq!(inputs.len(), 40);
+
+        let modes = [
+            LineEnding::Lf,
+            LineEnding::CrLf,
+            LineEnding::Cr,
+            LineEnding::Auto,
+        ];
+
+        for text in &inputs {
+            for line_ending in modes {
+                let expected = forward_lines(text, line_ending);
+
+                for cap in 1..5 {
+                    let lines = RawRevLines::with_capacity(cap, Cursor::new(text))
+                        .line_ending(line_ending)
+                        .collect::<Result<Vec<_>, _>>()?;
+
+                    assert_eq!(
+                        lines, expected,
+                        "{text:?} with {line_ending:?} and capacity {cap}"
+                    );
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn raw_handles_as_forward_reader() -> TestResult {
+        let text = "ABCD\n\nEF\r\nGH\nIJ\n";
+
+        for cap in 1..(text.len() + 1) {
+            for read in 0..7 {
+                let mut rev_lines = RawRevLines::with_capacity(cap, Cursor::new(text));
+                rev_lines.by_ref().take(read).for_each(drop);
+
+                let (mut reader, offset) = rev_lines.as_forward_reader()?;
+                let mut rest = String::new();
+                reader.read_to_string(&mut rest)?;
+
+                let expected = [16, 13, 10, 6, 5, 0, 0][read];
+                assert_eq!(
+                    offset, expected as u64,
+                    "after {read} lines at capacity {cap}"
+                );
+                assert_eq!(rest, text[expected..]);
+            }
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn raw_handles_runs_of_empty_lines() -> TestResult {
+        let text = b"\n\na\n\n\r\n\n\r\rb\n\n\n\r\n\r\n";
+        let modes = [
+            LineEnding::Lf,
+            LineEnding::CrLf,
+            LineEnding::Cr,
+            LineEnding::Auto,
+        ];
+
+        for line_ending in modes {
+            let expected = forward_lines(text, line_ending);
+
+            for cap in 1..(text.len() + 1) {
+                let lines = RawRevLines::with_capacity(cap, Cursor::new(text))
+                    .line_ending(line_ending)
+                    .collect::<Result<Vec<_>, _>>()?;
+
+                assert_eq!(lines, expected, "{line_ending:?} at capacity {cap}");
+            }
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn raw_handles_seeking_to_position() -> TestResult {
+        let cases = [
+            ("ABCD\n\nEF\r\nGH\nIJ\n", LineEnding::Lf),
+            ("\r\nAB\r\nC\nD\r\n\r\nEF", LineEnding::CrLf),
+            ("\nAB\rC\n\n", LineEnding::Auto),
+        ];
+
+        for (text, line_ending) in cases {
+            for cap in 1..(text.len() + 1) {
+                let mut rev_lines =
+                    RawRevLines::with_capacity(cap, Cursor::new(text)).line_ending(line_ending);
+                let start = rev_lines.position();
+                let all = rev_lines.by_ref().collect::<Result<Vec<_>, _>>()?;
+
+                rev_lines.seek_to_position(start)?;
+                assert_eq!(rev_lines.by_ref().collect::<Result<Vec<_>, _>>()?, all);
+
+                for bookmarked in 0..(all.len() + 1) {
+                    rev_lines.seek_to_position(start)?;
+                    rev_lines.by_ref().take(bookmarked).for_each(drop);
+                    let position = rev_lines.position();
+
+                    let rest = rev_lines.by_ref().collect::<Result<Vec<_>, _>>()?;
+                    assert_eq!(rest, all[bookmarked..], "{text:?} at capacity {cap}");
+
+                    rev_lines.seek_to_position(position)?;
+                    let resumed = rev_lines.by_ref().collect::<Result<Vec<_>, _>>()?;
+                    assert_eq!(resumed, rest, "{text:?} at capacity {cap}");
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_handles_require_trailing_newline() -> TestResult {
+        for cap in 1..5 {
+            let mut rev_lines =
+                RevLines::with_capacity(cap, Cursor::new("a\nb")).require_trailing_newline(true);
+            assert!(matches!(
+                rev_lines.next(),
+                Some(Err(RevLinesError::Io(error))) if error.kind() == std::io::ErrorKind::InvalidData
+            ));
+            assert!(rev_lines.next().is_none());
+
+            let lines = RevLines::with_capacity(cap, Cursor::new("a\nb\n"))
+                .require_trailing_newline(true)
+                .collect::<Result<Vec<_>, _>>()?;
+            assert_eq!(lines, vec!["b", "a"]);
+        }
+
+        let mut rev_lines = RevLines::new(Cursor::new("")).require_trailing_newline(true);
+        assert!(rev_lines.next().is_none());
+
+        Ok(())
     }
-}
 
-impl<R: Read + Seek> Iterator for RevLines<R> {
-    type Item = Result<String, RevLinesError>;
+    #[test]
+    fn raw_handles_crlf_split_by_the_first_read() -> TestResult {
+        let modes = [
+            LineEnding::Lf,
+            LineEnding::CrLf,
+            LineEnding::Auto,
+            LineEnding::ControlChars,
+        ];
 
-    fn next(&mut self) -> Option<Result<String, RevLinesError>> {
-        let line = match self.0.next_line().transpose()? {
-            Ok(line) => line,
-            Err(error) => return Some(Err(RevLinesError::Io(error))),
-        };
+        for line_ending in modes {
+            for cap in [1, 2] {
+                let mut rev_lines =
+                    RawRevLines::with_capacity(cap, Cursor::new("x\r\n")).line_ending(line_ending);
+
+                assert_eq!(rev_lines.next().transpose()?, Some(b"x".to_vec()));
+                assert_eq!(rev_lines.next().transpose()?, None);
+            }
+        }
 
-        Some(String::from_utf8(line).map_err(RevLinesError::InvalidUtf8))
+        Ok(())
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use std::io::{BufReader, Cursor};
+    #[test]
+    fn it_handles_join_continuations() -> TestResult {
+        let cases = [
+            ("a\\\nb\nc\n", vec!["c", "ab"]),
+            ("a\\\r\nb\\\nc\\\n\\\nd", vec!["abcd"]),
+            ("a\\\r\nb\\\nc\\\n\nd\n", vec!["d", "abc"]),
+            ("a\\\n", vec!["a\\"]),
+            ("a\\\n\n", vec!["a"]),
+            ("\\\\\nb", vec!["\\b"]),
+        ];
 
-    use crate::{RawRevLines, RevLines};
+        for (text, expected) in cases {
+            for cap in 1..(text.len() + 1) {
+                let lines = RevLines::with_capacity(cap, Cursor::new(text))
+                    .join_continuations(true)
+                    .collect::<Result<Vec<_>, _>>()?;
 
-    type TestResult = Result<(), Box<dyn std::error::Error>>;
+                assert_eq!(lines, expected, "{text:?} at capacity {cap}");
+            }
+        }
 
-    #[test]
-    fn raw_handles_empty_files() -> TestResult {
-        let file = Cursor::new(Vec::new());
-        let mut rev_lines = RawRevLines::new(file);
+        // The line read ahead is not skipped when continuing from a position
+        let mut rev_lines =
+            RawRevLines::with_capacity(3, Cursor::new("a\\\nb\nc\r\nd\n")).join_continuations(true);
+        assert_eq!(rev_lines.next().transpose()?, Some(b"d".to_vec()));
+        let position = rev_lines.position();
+        assert_eq!(rev_lines.next().transpose()?, Some(b"c".to_vec()));
 
-        assert!(rev_lines.next().transpose()?.is_none());
+        rev_lines.seek_to_position(position)?;
+        let lines = rev_lines.collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(lines, vec![b"c".to_vec(), b"ab".to_vec()]);
 
         Ok(())
     }
 
     #[test]
-    fn raw_handles_file_with_one_line() -> TestResult {
-        let text = b"ABCD\n".to_vec();
-        for cap in 1..(text.len() + 1) {
-            let file = Cursor::new(&text);
-            let mut rev_lines = RawRevLines::with_capacity(cap, file);
+    fn raw_handles_cr_handling() -> TestResult {
+        let cases = [
+            (CrHandling::StripBeforeLf, [&b"c"[..], b"a\rb"]),
+            (CrHandling::StripAll, [b"c", b"ab"]),
+            (CrHandling::Keep, [b"c\r", b"a\rb\r"]),
+        ];
 
-            assert_eq!(rev_lines.next().transpose()?, Some(b"ABCD".to_vec()));
-            assert_eq!(rev_lines.next().transpose()?, None);
+        for (handling, expected) in cases {
+            for cap in 1..9 {
+                let file = Cursor::new(b"a\rb\r\nc\r\n");
+                let lines = RawRevLines::with_capacity(cap, file)
+                    .cr_handling(handling)
+                    .collect::<Result<Vec<_>, _>>()?;
+
+                assert_eq!(lines, expected, "{handling:?} at capacity {cap}");
+            }
         }
 
         Ok(())
     }
 
     #[test]
-    fn raw_handles_file_with_multi_lines() -> TestResult {
-        let text = b"ABCDEF\nGHIJK\nLMNOPQRST\nUVWXYZ\n".to_vec();
-        for cap in 5..(text.len() + 1) {
-            let file = Cursor::new(b"ABCDEF\nGHIJK\nLMNOPQRST\nUVWXYZ\n".to_vec());
-            let mut rev_lines = RawRevLines::with_capacity(cap, file);
+    fn raw_handles_progress() -> TestResult {
+        let calls = Arc::new(Mutex::new(Vec::new()));
+        let progress = calls.clone();
+        let file = Cursor::new("ABCD\n\nEF\r\nGH\nIJ\n");
+        let rev_lines = RawRevLines::with_capacity(5, file)
+            .progress(move |processed, total| progress.lock().unwrap().push((processed, total)));
 
-            assert_eq!(rev_lines.next().transpose()?, Some(b"UVWXYZ".to_vec()));
-            assert_eq!(rev_lines.next().transpose()?, Some(b"LMNOPQRST".to_vec()));
-            assert_eq!(rev_lines.next().transpose()?, Some(b"GHIJK".to_vec()));
-            assert_eq!(rev_lines.next().transpose()?, Some(b"ABCDEF".to_vec()));
-            assert_eq!(rev_lines.next().transpose()?, None);
-        }
+        assert_eq!(rev_lines.count(), 5);
+        assert_eq!(
+            *calls.lock().unwrap(),
+            [(5, 16), (10, 16), (15, 16), (16, 16)]
+        );
+
+        let progress = calls.clone();
+        let rev_lines = RawRevLines::new(Cursor::new(""))
+            .progress(move |processed, total| progress.lock().unwrap().push((processed, total)));
+        assert_eq!(rev_lines.count(), 0);
+        assert_eq!(calls.lock().unwrap().len(), 4);
 
         Ok(())
     }
 
     #[test]
-    fn raw_handles_windows_file_with_multi_lines() -> TestResult {
-        let text = b"ABCDEF\r\nGHIJK\r\nLMNOP\rQRST\r\nUVWXYZ\r\n".to_vec();
-        for cap in 1..(text.len() + 1) {
-            let file = Cursor::new(&text);
-            let mut rev_lines = RawRevLines::with_capacity(cap, file);
+    fn raw_handles_trailing_bare_cr() -> TestResult {
+        let cases = [
+            (LineEnding::Lf, false, &b"abc\r"[..]),
+            (LineEnding::Lf, true, b"abc"),
+            (LineEnding::CrLf, false, b"abc\r"),
+            (LineEnding::CrLf, true, b"abc"),
+            (LineEnding::Cr, false, b"abc"),
+            (LineEnding::Cr, true, b"abc"),
+            (LineEnding::Auto, false, b"abc"),
+            (LineEnding::Auto, true, b"abc"),
+        ];
 
-            assert_eq!(rev_lines.next().transpose()?, Some(b"UVWXYZ".to_vec()));
-            assert_eq!(rev_lines.next().transpose()?, Some(b"LMNOP\rQRST".to_vec())); // bare CR not stripped
-            assert_eq!(rev_lines.next().transpose()?, Some(b"GHIJK".to_vec()));
-            assert_eq!(rev_lines.next().transpose()?, Some(b"ABCDEF".to_vec()));
-            assert_eq!(rev_lines.next().transpose()?, None);
+        for (line_ending, trim, expected) in cases {
+            for cap in 1..5 {
+                let file = Cursor::new(b"abc\r".to_vec());
+                let mut rev_lines = RawRevLines::with_capacity(cap, file)
+                    .line_ending(line_ending)
+                    .trim_trailing_cr(trim);
+
+                assert_eq!(rev_lines.next().transpose()?, Some(expected.to_vec()));
+                assert_eq!(rev_lines.next().transpose()?, None);
+            }
         }
 
         Ok(())
@@ -308,6 +5484,88 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn raw_reports_capacity() {
+        assert_eq!(RawRevLines::new(Cursor::new(Vec::new())).capacity(), 4096);
+        assert_eq!(
+            RawRevLines::with_capacity(5, Cursor::new(Vec::new())).capacity(),
+            5
+        );
+        assert_eq!(
+            RevLines::with_capacity(17, Cursor::new(Vec::new())).capacity(),
+            17
+        );
+    }
+
+    #[test]
+    fn raw_handles_fill_to_capacity_before_next() -> TestResult {
+        let file = Cursor::new(b"ABCDEF\nGHIJK\nLMNOPQRST\nUVWXYZ\n".to_vec());
+        let mut rev_lines = RawRevLines::with_capacity(5, file);
+
+        rev_lines.fill_to_capacity()?;
+        rev_lines.fill_to_capacity()?;
+
+        assert_eq!(rev_lines.next().transpose()?, Some(b"UVWXYZ".to_vec()));
+        assert_eq!(rev_lines.next().transpose()?, Some(b"LMNOPQRST".to_vec()));
+        assert_eq!(rev_lines.next().transpose()?, Some(b"GHIJK".to_vec()));
+        assert_eq!(rev_lines.next().transpose()?, Some(b"ABCDEF".to_vec()));
+        assert_eq!(rev_lines.next().transpose()?, None);
+
+        Ok(())
+    }
+
+    #[test]
+    fn raw_handles_take_while_prefix() -> TestResult {
+        let text = b"2023-01-01 a\n2023-01-02 b\n2023-01-03 c\n2023-01-04 d\n".to_vec();
+        for cap in 1..(text.len() + 1) {
+            let file = Cursor::new(&text);
+            let mut rev_lines = RawRevLines::with_capacity(cap, file)
+                .take_while_prefix(|line| line[..10] >= b"2023-01-03"[..]);
+
+            assert_eq!(
+                rev_lines.next().transpose()?,
+                Some(b"2023-01-04 d".to_vec())
+            );
+            assert_eq!(
+                rev_lines.next().transpose()?,
+                Some(b"2023-01-03 c".to_vec())
+            );
+            assert_eq!(rev_lines.next().transpose()?, None);
+            assert_eq!(rev_lines.next().transpose()?, None);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn raw_handles_truncate_long_lines() -> TestResult {
+        let text = b"ABCDEF\nGHIJK\nLMNOPQRST\nUVWXYZ\n".to_vec();
+        for cap in 1..(text.len() + 1) {
+            let file = Cursor::new(&text);
+            let mut rev_lines = RawRevLines::with_capacity(cap, file).truncate_long_lines(6);
+
+            assert_eq!(
+                rev_lines.next().transpose()?,
+                Some((b"UVWXYZ".to_vec(), false))
+            );
+            assert_eq!(
+                rev_lines.next().transpose()?,
+                Some((b"OPQRST".to_vec(), true))
+            );
+            assert_eq!(
+                rev_lines.next().transpose()?,
+                Some((b"GHIJK".to_vec(), false))
+            );
+            assert_eq!(
+                rev_lines.next().transpose()?,
+                Some((b"ABCDEF".to_vec(), false))
+            );
+            assert_eq!(rev_lines.next().transpose()?, None);
+        }
+
+        Ok(())
+    }
+
     #[test]
     fn it_handles_empty_files() -> TestResult {
         let file = Cursor::new(Vec::new());
@@ -387,4 +5645,359 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn it_handles_file_with_line_feed_inside_multibyte_sequence() -> TestResult {
+        // 0xC2 0xA9 is a valid two byte sequence, split apart by a line feed
+        let text = b"ABC\nDE\xC2\n\xA9FG\n".to_vec();
+        let mut rev_lines = RevLines::new(Cursor::new(&text)).validate_boundaries(true);
+
+        assert!(matches!(
+            rev_lines.next(),
+            Some(Err(RevLinesError::SplitSequence { offset: 7 }))
+        ));
+        assert!(matches!(
+            rev_lines.next(),
+            Some(Err(RevLinesError::SplitSequence { offset: 7 }))
+        ));
+        assert_eq!(rev_lines.next().transpose()?, Some("ABC".to_string()));
+        assert_eq!(rev_lines.next().transpose()?, None);
+
+        let mut rev_lines = RevLines::new(Cursor::new(&text));
+
+        assert!(matches!(
+            rev_lines.next(),
+            Some(Err(RevLinesError::InvalidUtf8 { .. }))
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_validates_boundaries_of_invalid_utf8_without_split_sequences() -> TestResult {
+        let file = Cursor::new(vec![
+            b'A', b'B', b'C', b'D', b'E', b'F', b'\n', // some valid UTF-8 in this line
+            b'X', 252, 253, 254, b'Y', b'\n', // invalid UTF-8 in this line
+            b'G', b'H', b'I', b'J', b'K', b'\n', // some more valid UTF-8 at the end
+        ]);
+        let mut rev_lines = RevLines::new(file).validate_boundaries(true);
+
+        assert_eq!(rev_lines.next().transpose()?, Some("GHIJK".to_string()));
+        assert!(matches!(
+            rev_lines.next(),
+            Some(Err(RevLinesError::InvalidUtf8 { .. }))
+        ));
+        assert_eq!(rev_lines.next().transpose()?, Some("ABCDEF".to_string()));
+        assert_eq!(rev_lines.next().transpose()?, None);
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_handles_tail_forward() -> TestResult {
+        let text = b"ABCDEF\nGHIJK\nLMNOPQRST\nUVWXYZ\n".to_vec();
+        let mut rev_lines = RevLines::tail_forward(Cursor::new(&text), 3);
+
+        assert_eq!(rev_lines.next().transpose()?, Some("GHIJK".to_string()));
+        assert_eq!(rev_lines.next().transpose()?, Some("LMNOPQRST".to_string()));
+        assert_eq!(rev_lines.next().transpose()?, Some("UVWXYZ".to_string()));
+        assert_eq!(rev_lines.next().transpose()?, None);
+
+        let lines =
+            RevLines::tail_forward(Cursor::new(&text), 10).collect::<Result<Vec<_>, _>>()?;
+
+        assert_eq!(lines, vec!["ABCDEF", "GHIJK", "LMNOPQRST", "UVWXYZ"]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_handles_lines_indexed() -> TestResult {
+        let text = b"ABCDEF\nGHIJK\n\nUVWXYZ\n".to_vec();
+        let mut rev_lines = RevLines::with_capacity(3, Cursor::new(&text));
+
+        // Lines already read before the prepass are counted and yielded again
+        assert_eq!(rev_lines.next().transpose()?, Some("UVWXYZ".to_string()));
+
+        let lines = rev_lines
+            .lines_indexed()?
+            .map(|(number, line)| Ok((number, line?)))
+            .collect::<Result<Vec<_>, RevLinesError>>()?;
+
+        assert_eq!(
+            lines,
+            vec![
+                (4, "UVWXYZ".to_string()),
+                (3, "".to_string()),
+                (2, "GHIJK".to_string()),
+                (1, "ABCDEF".to_string())
+            ]
+        );
+
+        let lines = RevLines::new(Cursor::new("a\nb\nc\nd\ne\n"))
+            .max_lines(2)
+            .lines_indexed()?
+            .map(|(number, line)| Ok((number, line?)))
+            .collect::<Result<Vec<_>, RevLinesError>>()?;
+        assert_eq!(lines, vec![(5, "e".to_string()), (4, "d".to_string())]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_handles_lines_numbered() -> TestResult {
+        let readme = std::fs::read_to_string("README.md")?;
+        let mut expected = readme
+            .lines()
+            .zip(1..)
+            .map(|(line, number)| (line.to_string(), number))
+            .collect::<Vec<_>>();
+        expected.reverse();
+
+        for cap in [1, 7, 4096] {
+            let file = std::fs::File::open("README.md")?;
+            let lines = RevLines::with_capacity(cap, file)
+                .lines_numbered()
+                .collect::<Result<Vec<_>, _>>()?;
+            assert_eq!(lines, expected, "capacity {cap}");
+        }
+
+        let text = b"ABCDEF\nGHIJK\n\nUVWXYZ\n".to_vec();
+        let mut numbered = RevLines::new(Cursor::new(&text)).lines_numbered();
+        assert_eq!(
+            numbered.next().transpose()?,
+            Some(("UVWXYZ".to_string(), 4))
+        );
+        assert_eq!(numbered.next().transpose()?, Some(("".to_string(), 3)));
+
+        let lines = RevLines::new(Cursor::new("a\nb\nc\nd\ne\n"))
+            .max_lines(2)
+            .lines_numbered()
+            .collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(lines, vec![("e".to_string(), 5), ("d".to_string(), 4)]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_handles_reverse_lines_count_bytes() -> TestResult {
+        let text = b"alpha\nbeta\ngamma\n".to_vec();
+
+        for cap in 1..(text.len() + 1) {
+            let mut rev_lines = RevLines::with_capacity(cap, Cursor::new(&text));
+            assert_eq!(rev_lines.next().transpose()?, Some("gamma".to_string()));
+
+            // The window starts right at "beta"
+            assert_eq!(rev_lines.reverse_lines_count_bytes(11)?, (2, 11));
+            // The window starts inside "beta", which is not counted
+            assert_eq!(rev_lines.reverse_lines_count_bytes(9)?, (1, 6));
+            assert_eq!(rev_lines.reverse_lines_count_bytes(100)?, (3, 17));
+            assert_eq!(rev_lines.reverse_lines_count_bytes(0)?, (0, 0));
+
+            // Iteration continues where it was
+            assert_eq!(rev_lines.next().transpose()?, Some("beta".to_string()));
+        }
+
+        let mut rev_lines = RevLines::new(Cursor::new(&text)).max_lines(1);
+        assert_eq!(rev_lines.reverse_lines_count_bytes(100)?, (3, 17));
+        assert_eq!(rev_lines.next().transpose()?, Some("gamma".to_string()));
+        assert_eq!(rev_lines.reverse_lines_count_bytes(100)?, (3, 17));
+        assert_eq!(rev_lines.next().transpose()?, None);
+
+        Ok(())
+    }
+
+    #[test]
+    fn raw_handles_fixed_width_records() -> TestResult {
+        let records = RawRevLines::with_fixed_width(4, Cursor::new("AAAA\nBBBCCC\r"))
+            .collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(records, [&b"CCC\r"[..], b"\nBBB", b"AAAA"]);
+
+        let mut rev_lines = RawRevLines::with_fixed_width(4, Cursor::new("AAAABBBBCC"))
+            .line_ending(LineEnding::Byte(b'B'));
+        assert_eq!(rev_lines.next_line_at()?, Some((8, b"CC".to_vec())));
+        assert_eq!(rev_lines.next_line_at()?, Some((4, b"BBBB".to_vec())));
+        assert_eq!(rev_lines.next_line_at()?, Some((0, b"AAAA".to_vec())));
+        assert_eq!(rev_lines.next_line_at()?, None);
+
+        let records =
+            RevLines::with_fixed_width(3, Cursor::new("héé")).collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(records, ["é", "hé"]);
+        assert_eq!(
+            RawRevLines::with_fixed_width(0, Cursor::new("AAAA")).count(),
+            0
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn raw_handles_scan_backwards_for() -> TestResult {
+        let text = b"key=value=x\nlast=1\n".to_vec();
+
+        for cap in 1..(text.len() + 1) {
+            let mut rev_lines = RawRevLines::with_capacity(cap, Cursor::new(&text));
+            let is_equals = |byte| byte == b'=';
+
+            assert_eq!(rev_lines.scan_backwards_for(is_equals)?, Some(16));
+            assert_eq!(rev_lines.next().transpose()?, Some(b"last".to_vec()));
+
+            assert_eq!(rev_lines.scan_backwards_for(is_equals)?, Some(9));
+            assert_eq!(rev_lines.scan_backwards_for(is_equals)?, Some(3));
+            assert_eq!(rev_lines.next().transpose()?, Some(b"key".to_vec()));
+            assert_eq!(rev_lines.scan_backwards_for(is_equals)?, None);
+            assert_eq!(rev_lines.next().transpose()?, None);
+
+            let mut rev_lines = RawRevLines::with_capacity(cap, Cursor::new(&text));
+            assert_eq!(
+                rev_lines.scan_backwards_for(|byte| byte == b'\n')?,
+                Some(18)
+            );
+            assert_eq!(rev_lines.scan_backwards_for(|byte| byte == b'#')?, None);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn raw_handles_read_until_rev() -> TestResult {
+        let text = b"id=1;id=22;;id=333".to_vec();
+
+        for cap in 1..(text.len() + 1) {
+            let mut rev_lines = RawRevLines::with_capacity(cap, Cursor::new(&text));
+            let mut records = Vec::new();
+
+            loop {
+                let mut record = Vec::new();
+                match rev_lines.read_until_rev(b';', &mut record)? {
+                    0 => break,
+                    len => assert_eq!(len, record.len()),
+                }
+                records.push(record);
+            }
+
+            assert_eq!(records, [&b"id=333"[..], b";", b"id=22;", b"id=1;"]);
+            records.reverse();
+            assert_eq!(records.concat(), text, "capacity {cap}");
+        }
+
+        let mut rev_lines = RawRevLines::new(Cursor::new("a\r\nb\r\n"));
+        let mut buf = b"start:".to_vec();
+        assert_eq!(rev_lines.read_until_rev(b'\n', &mut buf)?, 3);
+        assert_eq!(buf, b"start:b\r\n");
+
+        let mut rev_lines = RawRevLines::new(Cursor::new("a\nb\nc\n"));
+        assert_eq!(rev_lines.next().transpose()?, Some(b"c".to_vec()));
+        buf.clear();
+        assert_eq!(rev_lines.read_until_rev(b'\n', &mut buf)?, 2);
+        assert_eq!(buf, b"b\n");
+        assert_eq!(rev_lines.next().transpose()?, Some(b"a".to_vec()));
+
+        Ok(())
+    }
+
+    #[test]
+    fn raw_handles_delimiters() -> TestResult {
+        let text = b"a\0bb\0ccc\0".to_vec();
+        let lines =
+            RawRevLines::with_delimiter(0, Cursor::new(&text)).collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(lines, [&b"ccc"[..], b"bb", b"a"]);
+
+        for cap in 1..(text.len() + 1) {
+            let lines = RawRevLines::with_capacity_and_delimiter(cap, 0, Cursor::new(&text))
+                .collect::<Result<Vec<_>, _>>()?;
+            assert_eq!(lines, [&b"ccc"[..], b"bb", b"a"], "capacity {cap}");
+        }
+
+        // Carriage returns are only stripped when splitting on line feeds
+        let lines = RevLines::with_delimiter(b';', Cursor::new("a\r;b\r\n;"))
+            .collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(lines, ["b\r\n", "a\r"]);
+        let lines = RevLines::with_capacity_and_delimiter(2, b'\n', Cursor::new("a\r\nb\r\n"))
+            .collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(lines, ["b", "a"]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn raw_handles_count_lines() -> TestResult {
+        let text = b"ABCDEF\nGHIJK\nLMNOPQRST\nUVWXYZ\n".to_vec();
+        for cap in 1..(text.len() + 1) {
+            let mut rev_lines = RawRevLines::with_capacity(cap, Cursor::new(&text));
+
+            assert_eq!(rev_lines.next().transpose()?, Some(b"UVWXYZ".to_vec()));
+            assert_eq!(rev_lines.count_lines()?, 3);
+            assert_eq!(rev_lines.next().transpose()?, None);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_handles_filter_map_lines_with_invalid_utf8() -> TestResult {
+        let file = Cursor::new(vec![
+            b'1', b'\n', b'X', 252, b'\n', b'a', b'\n', b'2', b'\n', // mixed lines
+        ]);
+        let mut numbers = RevLines::new(file).filter_map_lines(|line| line.parse::<u8>().ok());
+
+        assert_eq!(numbers.next().transpose()?, Some(2));
+        assert!(matches!(
+            numbers.next(),
+            Some(Err(RevLinesError::InvalidUtf8 { .. }))
+        ));
+        assert_eq!(numbers.next().transpose()?, Some(1));
+        assert_eq!(numbers.next().transpose()?, None);
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_handles_chain_with_bom_per_reader() -> TestResult {
+        let older = Cursor::new(b"\xEF\xBB\xBFABC\nDEF\n".to_vec());
+        let newer = Cursor::new(b"\xEF\xBB\xBFGHI\nJKL".to_vec());
+        let mut rev_lines = RevLinesChain::new([older, newer]).strip_bom(true);
+
+        assert_eq!(rev_lines.next().transpose()?, Some("JKL".to_string()));
+        assert_eq!(rev_lines.next().transpose()?, Some("GHI".to_string()));
+        assert_eq!(rev_lines.next().transpose()?, Some("DEF".to_string()));
+        assert_eq!(rev_lines.next().transpose()?, Some("ABC".to_string()));
+        assert_eq!(rev_lines.next().transpose()?, None);
+
+        let older = Cursor::new(b"\xEF\xBB\xBFABC\n".to_vec());
+        let newer = Cursor::new(b"\xEF\xBB\xBFGHI\n".to_vec());
+        let mut rev_lines = RevLinesChain::new([older, newer]);
+
+        assert_eq!(
+            rev_lines.next().transpose()?,
+            Some("\u{feff}GHI".to_string())
+        );
+        assert_eq!(
+            rev_lines.next().transpose()?,
+            Some("\u{feff}ABC".to_string())
+        );
+        assert_eq!(rev_lines.next().transpose()?, None);
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_handles_probe() -> TestResult {
+        let file = Cursor::new(b"ABCDEF\r\nGHIJK\nLMNOPQRST\nUVWXYZ\n".to_vec());
+        assert_eq!(
+            RevLines::probe(file)?,
+            (Some("ABCDEF".to_string()), Some("UVWXYZ".to_string()))
+        );
+
+        let file = Cursor::new(b"ABCDEF".to_vec());
+        assert_eq!(
+            RevLines::probe(file)?,
+            (Some("ABCDEF".to_string()), Some("ABCDEF".to_string()))
+        );
+
+        let file = Cursor::new(Vec::new());
+        assert_eq!(RevLines::probe(file)?, (None, None));
+
+        Ok(())
+    }
 }
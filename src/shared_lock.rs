@@ -0,0 +1,64 @@
+//! Read files in reverse while holding an advisory lock on them.
+//!
+//! Enabled with the `fs2` feature.
+
+use std::fs::File;
+use std::io;
+
+use fs2::FileExt;
+
+use crate::RevLines;
+
+impl RevLines<File> {
+    /// Hold a shared lock on `file` while `f` reads its lines in reverse, so processes
+    /// which take an exclusive lock before rotating or rewriting it wait until `f` returns.
+    ///
+    /// This uses [`FileExt::lock_shared`]. On Unix the lock is advisory, so it only keeps
+    /// out processes which lock the file themselves. On Windows it is mandatory and blocks
+    /// writes by everyone else. The lock is released by closing the file, right after `f`
+    /// returns or while unwinding if `f` panics.
+    pub fn with_shared_lock<T, F: FnOnce(&mut RevLines<File>) -> T>(
+        file: File,
+        f: F,
+    ) -> io::Result<T> {
+        // Called through the trait, as `File` has inherent locking methods in newer Rust
+        FileExt::lock_shared(&file)?;
+
+        let mut rev_lines = RevLines::new(file);
+        Ok(f(&mut rev_lines))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::File;
+
+    use fs2::FileExt;
+
+    use crate::RevLines;
+
+    type TestResult = Result<(), Box<dyn std::error::Error>>;
+
+    #[test]
+    fn it_handles_shared_locks() -> TestResult {
+        let path = std::env::temp_dir().join(format!("rev_lines-lock-{}", std::process::id()));
+        std::fs::write(&path, "first\nsecond\n")?;
+
+        let (lines, locked) = RevLines::with_shared_lock(File::open(&path)?, |rev_lines| {
+            let lines = rev_lines.collect::<Result<Vec<_>, _>>();
+            // An exclusive lock conflicts with the shared one while it is held
+            let locked =
+                File::open(&path).map(|other| FileExt::try_lock_exclusive(&other).is_err());
+            (lines, locked)
+        })?;
+        assert_eq!(lines?, ["second", "first"]);
+        assert!(locked?);
+
+        let other = File::open(&path)?;
+        assert!(FileExt::try_lock_exclusive(&other).is_ok());
+        drop(other);
+        std::fs::remove_file(&path)?;
+
+        Ok(())
+    }
+}
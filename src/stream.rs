@@ -0,0 +1,171 @@
+//! Async [`Stream`] of the lines of a sync reader.
+//!
+//! Enabled with the `stream` feature. Unlike the `tokio1` module, the reader stays a
+//! sync [`Read`] + [`Seek`] reader, such as a `std::fs::File`, and the blocking reads
+//! run on tokio's blocking thread pool.
+
+use std::collections::VecDeque;
+use std::future::Future;
+use std::io::{Read, Seek};
+use std::pin::Pin;
+use std::task::{ready, Context, Poll};
+
+use futures_core::Stream;
+use tokio::task::{self, JoinHandle};
+
+use crate::{LineDecoder, RevLines, RevLinesError, Utf8Decoder};
+
+// Lines read per blocking task, so not every line pays for a round trip to the thread pool
+const BATCH_LEN: usize = 64;
+
+type Batch = VecDeque<Result<String, RevLinesError>>;
+
+impl<R, D> RevLines<R, D>
+where
+    R: Read + Seek + Send + 'static,
+    D: LineDecoder + Send + 'static,
+{
+    /// Turn the iterator into a [`Stream`] of the same items for async consumers.
+    ///
+    /// Lines are read in batches with [`tokio::task::spawn_blocking`], so the stream must
+    /// be polled from within a tokio runtime.
+    ///
+    /// ```
+    /// # tokio::runtime::Builder::new_current_thread().build().unwrap().block_on(async {
+    /// use std::future::poll_fn;
+    /// use std::io::Cursor;
+    /// use std::pin::Pin;
+    ///
+    /// use futures_core::Stream;
+    /// use rev_lines::RevLines;
+    ///
+    /// let mut stream = RevLines::new(Cursor::new("first\nsecond\n")).into_stream();
+    /// let mut lines = Vec::new();
+    ///
+    /// while let Some(line) = poll_fn(|cx| Pin::new(&mut stream).poll_next(cx)).await {
+    ///     lines.push(line.unwrap());
+    /// }
+    ///
+    /// assert_eq!(lines, ["second", "first"]);
+    /// # });
+    /// ```
+    pub fn into_stream(self) -> RevLinesStream<R, D> {
+        RevLinesStream {
+            rev_lines: Some(self),
+            lines: VecDeque::new(),
+            reading: None,
+        }
+    }
+}
+
+/// Stream returned by [`RevLines::into_stream`]
+pub struct RevLinesStream<R, D = Utf8Decoder> {
+    // None while a batch is being read and once the lines ran out
+    rev_lines: Option<RevLines<R, D>>,
+    lines: Batch,
+    reading: Option<JoinHandle<(RevLines<R, D>, Batch)>>,
+}
+
+// Nothing is pinned structurally, the iterator is only ever moved into blocking tasks
+impl<R, D> Unpin for RevLinesStream<R, D> {}
+
+impl<R, D> Stream for RevLinesStream<R, D>
+where
+    R: Read + Seek + Send + 'static,
+    D: LineDecoder + Send + 'static,
+{
+    type Item = Result<String, RevLinesError>;
+
+    fn poll_next(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<String, RevLinesError>>> {
+        let this = self.get_mut();
+
+        loop {
+            if let Some(line) = this.lines.pop_front() {
+                return Poll::Ready(Some(line));
+            }
+
+            if let Some(reading) = this.reading.as_mut() {
+                let result = ready!(Pin::new(reading).poll(cx));
+                this.reading = None;
+
+                let (rev_lines, lines) = match result {
+                    Ok(batch) => batch,
+                    Err(error) if error.is_panic() => std::panic::resume_unwind(error.into_panic()),
+                    // The runtime is shutting down
+                    Err(_) => return Poll::Ready(None),
+                };
+                if lines.is_empty() {
+                    return Poll::Ready(None);
+                }
+
+                this.rev_lines = Some(rev_lines);
+                this.lines = lines;
+                continue;
+            }
+
+            let Some(mut rev_lines) = this.rev_lines.take() else {
+                return Poll::Ready(None);
+            };
+            let mut lines = std::mem::take(&mut this.lines);
+
+            this.reading = Some(task::spawn_blocking(move || {
+                lines.extend(rev_lines.by_ref().take(BATCH_LEN));
+                (rev_lines, lines)
+            }));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::future::poll_fn;
+    use std::io::Cursor;
+    use std::pin::Pin;
+
+    use futures_core::Stream;
+
+    use crate::{RevLines, RevLinesError};
+
+    type TestResult = Result<(), Box<dyn std::error::Error>>;
+
+    async fn collect<S>(mut stream: S) -> Result<Vec<String>, RevLinesError>
+    where
+        S: Stream<Item = Result<String, RevLinesError>> + Unpin,
+    {
+        let mut lines = Vec::new();
+        while let Some(line) = poll_fn(|cx| Pin::new(&mut stream).poll_next(cx)).await {
+            lines.push(line?);
+        }
+
+        Ok(lines)
+    }
+
+    #[tokio::test]
+    async fn it_handles_streams() -> TestResult {
+        let text = (0..200).map(|n| format!("{n}\n")).collect::<String>();
+        let lines = collect(RevLines::new(Cursor::new(text)).into_stream()).await?;
+
+        let expected = (0..200).rev().map(|n| n.to_string()).collect::<Vec<_>>();
+        assert_eq!(lines, expected);
+
+        let lines = collect(RevLines::new(Cursor::new("")).into_stream()).await?;
+        assert!(lines.is_empty());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn it_handles_errors_in_streams() -> TestResult {
+        let stream = RevLines::new(Cursor::new(b"ok\n\xff\nok\n".to_vec())).into_stream();
+
+        assert!(matches!(
+            collect(stream).await,
+            Err(RevLinesError::InvalidUtf8 { offset: 3, .. })
+        ));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,84 @@
+//! Helpers for testing code built on top of `rev_lines`.
+//!
+//! Enabled with the `testing` feature. These helpers panic on failure, so they
+//! are meant to be called from tests rather than library code.
+
+use std::io::Cursor;
+
+use crate::{RawRevLines, RevLines};
+
+/// Collect the lines of `bytes` in reverse, using the default capacity.
+///
+/// Lines with invalid UTF-8 are decoded lossily.
+///
+/// ```
+/// use rev_lines::testing::reversed_lines_of;
+///
+/// assert_eq!(reversed_lines_of(b"a\nb\nc\n"), vec!["c", "b", "a"]);
+/// ```
+pub fn reversed_lines_of(bytes: &[u8]) -> Vec<String> {
+    reversed_lines_with_capacity(bytes, crate::DEFAULT_SIZE)
+}
+
+/// Collect the lines of `bytes` in reverse, reading `cap` bytes at a time.
+///
+/// Lines with invalid UTF-8 are decoded lossily.
+pub fn reversed_lines_with_capacity(bytes: &[u8], cap: usize) -> Vec<String> {
+    RawRevLines::with_capacity(cap, Cursor::new(bytes))
+        .map(|line| {
+            let line = line.expect("reading from an in-memory cursor cannot fail");
+            String::from_utf8_lossy(&line).into_owned()
+        })
+        .collect()
+}
+
+/// Assert that reading `bytes` in reverse yields the same lines for every capacity
+/// from 1 up to the length of the input, and return those lines.
+///
+/// Lines are read with [`RawRevLines`] and decoded lossily, the same way as
+/// [`reversed_lines_with_capacity`], so this checks line splitting rather than strict
+/// UTF-8 decoding. The output must never depend on where buffer boundaries fall, so
+/// this is a cheap way to exercise every split of a fixture.
+///
+/// ```
+/// use rev_lines::testing::assert_all_capacities_agree;
+///
+/// let lines = assert_all_capacities_agree(b"one\r\ntwo\r\nthree\r\n");
+///
+/// assert_eq!(lines, vec!["three", "two", "one"]);
+/// ```
+pub fn assert_all_capacities_agree(bytes: &[u8]) -> Vec<String> {
+    let expected = reversed_lines_of(bytes);
+
+    for cap in 1..=bytes.len() {
+        let lines = reversed_lines_with_capacity(bytes, cap);
+
+        assert_eq!(
+            lines, expected,
+            "reading with a capacity of {cap} bytes yielded different lines"
+        );
+    }
+
+    expected
+}
+
+/// Collect the lines of `bytes` in reverse with `RevLines`, panicking on invalid UTF-8.
+pub fn reversed_strict_lines_of(bytes: &[u8]) -> Vec<String> {
+    RevLines::new(Cursor::new(bytes))
+        .collect::<Result<_, _>>()
+        .expect("fixture should be valid UTF-8")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{assert_all_capacities_agree, reversed_strict_lines_of};
+
+    #[test]
+    fn it_agrees_across_capacities() {
+        let text = b"ABCDEF\nGHIJK\r\nLMNOP\rQRST\n\nUVWXYZ";
+        let lines = assert_all_capacities_agree(text);
+
+        assert_eq!(lines, reversed_strict_lines_of(text));
+        assert_eq!(lines, vec!["UVWXYZ", "", "LMNOP\rQRST", "GHIJK", "ABCDEF"]);
+    }
+}
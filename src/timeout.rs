@@ -0,0 +1,277 @@
+//! [`TimeoutReader`], which bounds how long a single read or seek may take.
+//!
+//! Enabled with the `timeout` feature.
+
+use std::io::{self, Read, Seek, SeekFrom};
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+
+use crate::{RawRevLines, RevLines};
+
+impl<R: Read + Seek + Send + 'static> RawRevLines<R> {
+    /// Create a new `RawRevLines` struct which fails with an `io::Error` of kind `TimedOut`
+    /// when a single read or seek of `reader` takes longer than `timeout`, e.g. for readers
+    /// on network filesystems which can hang. See [`TimeoutReader`] for how this works.
+    ///
+    /// Timeouts are errors of kind `TimedOut`, which [`IoErrorPolicy::SkipLine`] recovers
+    /// from by skipping the line that was being read.
+    ///
+    /// [`IoErrorPolicy::SkipLine`]: crate::IoErrorPolicy::SkipLine
+    pub fn with_read_timeout(timeout: Duration, reader: R) -> RawRevLines<TimeoutReader> {
+        RawRevLines::new(TimeoutReader::new(reader, timeout))
+    }
+}
+
+impl<R: Read + Seek + Send + 'static> RevLines<R> {
+    /// Create a new `RevLines` which fails with [`RevLinesError::Io`] of kind `TimedOut`
+    /// when a single read or seek takes longer than `timeout`.
+    /// See [`RawRevLines::with_read_timeout`].
+    ///
+    /// [`RevLinesError::Io`]: crate::RevLinesError::Io
+    pub fn with_read_timeout(timeout: Duration, reader: R) -> RevLines<TimeoutReader> {
+        RevLines::from(RawRevLines::with_read_timeout(timeout, reader))
+    }
+}
+
+/// Reader returned by [`RawRevLines::with_read_timeout`], which fails reads and seeks
+/// taking longer than a timeout with an `io::Error` of kind `TimedOut`.
+///
+/// Blocking reads can't be interrupted, so the wrapped reader is moved to a helper thread
+/// which runs every read and seek, while the caller waits for the result up to the timeout.
+/// A call which timed out keeps running in the background. The next call first waits for
+/// it to finish and then seeks back to where it started, so the failed call can simply be
+/// retried. If the wrapped reader never returns, its thread is never freed either.
+///
+pub struct TimeoutReader {
+    requests: mpsc::Sender<TimeoutRequest>,
+    responses: mpsc::Receiver<TimeoutResponse>,
+    timeout: Duration,
+    // A call timed out and its response is still to come
+    stale: bool,
+}
+
+enum TimeoutRequest {
+    Read(usize),
+    Seek(SeekFrom),
+}
+
+enum TimeoutReply {
+    Read(Vec<u8>),
+    Seek(u64),
+}
+
+// The position before the call, along with its result
+type TimeoutResponse = (io::Result<u64>, io::Result<TimeoutReply>);
+
+impl TimeoutReader {
+    /// Wrap `reader`, failing any single read or seek which takes longer than `timeout`.
+    pub fn new<R: Read + Seek + Send + 'static>(mut reader: R, timeout: Duration) -> TimeoutReader {
+        let (requests, request_receiver) = mpsc::channel();
+        let (response_sender, responses) = mpsc::channel();
+
+        thread::spawn(move || {
+            for request in request_receiver {
+                let position = reader.stream_position();
+                let result = match request {
+                    TimeoutRequest::Read(len) => {
+                        let mut bytes = vec![0; len];
+                        reader.read(&mut bytes).map(|read| {
+                            bytes.truncate(read);
+                            TimeoutReply::Read(bytes)
+                        })
+                    }
+                    TimeoutRequest::Seek(pos) => reader.seek(pos).map(TimeoutReply::Seek),
+                };
+
+                if response_sender.send((position, result)).is_err() {
+                    break;
+                }
+            }
+        });
+
+        TimeoutReader {
+            requests,
+            responses,
+            timeout,
+            stale: false,
+        }
+    }
+
+    fn call(&mut self, request: TimeoutRequest) -> io::Result<TimeoutReply> {
+        self.settle()?;
+        self.requests
+            .send(request)
+            .map_err(|_| io::Error::other("reader thread stopped"))?;
+
+        self.receive().map(|(_, result)| result)?
+    }
+
+    /// Wait for a call which timed out before and undo it
+    fn settle(&mut self) -> io::Result<()> {
+        if !self.stale {
+            return Ok(());
+        }
+
+        let (position, _) = self.receive()?;
+        self.stale = false;
+        self.call(TimeoutRequest::Seek(SeekFrom::Start(position?)))
+            .map(drop)
+    }
+
+    fn receive(&mut self) -> io::Result<TimeoutResponse> {
+        match self.responses.recv_timeout(self.timeout) {
+            Ok(response) => Ok(response),
+            Err(mpsc::RecvTimeoutError::Timeout) => {
+                self.stale = true;
+                Err(io::Error::new(
+                    io::ErrorKind::TimedOut,
+                    format!("reader did not respond within {:?}", self.timeout),
+                ))
+            }
+            Err(mpsc::RecvTimeoutError::Disconnected) => {
+                Err(io::Error::other("reader thread stopped"))
+            }
+        }
+    }
+}
+
+impl Read for TimeoutReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self.call(TimeoutRequest::Read(buf.len()))? {
+            TimeoutReply::Read(bytes) => {
+                buf[..bytes.len()].copy_from_slice(&bytes);
+                Ok(bytes.len())
+            }
+            TimeoutReply::Seek(_) => unreachable!("a read is answered with bytes"),
+        }
+    }
+}
+
+impl Seek for TimeoutReader {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        match self.call(TimeoutRequest::Seek(pos))? {
+            TimeoutReply::Seek(position) => Ok(position),
+            TimeoutReply::Read(_) => unreachable!("a seek is answered with a position"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{self, BufReader, Cursor, Read, Seek, SeekFrom};
+    use std::sync::atomic::AtomicUsize;
+    use std::sync::atomic::Ordering::SeqCst;
+    use std::sync::{mpsc, Arc};
+    use std::time::Duration;
+
+    use super::TimeoutReader;
+    use crate::{IoErrorPolicy, RawRevLines, RevLines, RevLinesError};
+
+    type TestResult = Result<(), Box<dyn std::error::Error>>;
+
+    /// Blocks every read while `blocked_reads` is above zero, counting it down, until
+    /// a read is released
+    struct BlockingReader {
+        inner: Cursor<&'static str>,
+        blocked_reads: Arc<AtomicUsize>,
+        released: mpsc::Receiver<()>,
+    }
+
+    impl Read for BlockingReader {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let decrement = |reads: usize| reads.checked_sub(1);
+            if self
+                .blocked_reads
+                .fetch_update(SeqCst, SeqCst, decrement)
+                .is_ok()
+            {
+                // Dropping the sender releases every read
+                let _ = self.released.recv();
+            }
+            self.inner.read(buf)
+        }
+    }
+
+    impl Seek for BlockingReader {
+        fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+            self.inner.seek(pos)
+        }
+    }
+
+    /// Releases the blocked read each time a call times out, so the next call finds it done
+    struct ReleaseOnTimeout {
+        inner: TimeoutReader,
+        release: mpsc::Sender<()>,
+    }
+
+    impl ReleaseOnTimeout {
+        fn release_on_timeout<T>(&self, result: io::Result<T>) -> io::Result<T> {
+            if matches!(&result, Err(error) if error.kind() == io::ErrorKind::TimedOut) {
+                let _ = self.release.send(());
+            }
+            result
+        }
+    }
+
+    impl Read for ReleaseOnTimeout {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let result = self.inner.read(buf);
+            self.release_on_timeout(result)
+        }
+    }
+
+    impl Seek for ReleaseOnTimeout {
+        fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+            let result = self.inner.seek(pos);
+            self.release_on_timeout(result)
+        }
+    }
+
+    fn blocking_rev_lines(blocked_reads: &Arc<AtomicUsize>) -> RevLines<ReleaseOnTimeout> {
+        let (release, released) = mpsc::channel();
+        let file = BlockingReader {
+            inner: Cursor::new("AAA\nBBB\nCCC\n"),
+            blocked_reads: blocked_reads.clone(),
+            released,
+        };
+        // Blocked reads always time out, the timeout only has to be long enough for the others
+        let file = ReleaseOnTimeout {
+            inner: TimeoutReader::new(file, Duration::from_millis(500)),
+            release,
+        };
+
+        RevLines::from(RawRevLines::from_parts(
+            Vec::with_capacity(4),
+            Vec::new(),
+            BufReader::with_capacity(0, file),
+        ))
+    }
+
+    #[test]
+    fn it_handles_read_timeouts() -> TestResult {
+        let blocked_reads = Arc::new(AtomicUsize::new(usize::MAX));
+        let mut rev_lines = blocking_rev_lines(&blocked_reads);
+        assert!(matches!(
+            rev_lines.next(),
+            Some(Err(RevLinesError::Io(error))) if error.kind() == io::ErrorKind::TimedOut
+        ));
+
+        let blocked_reads = Arc::new(AtomicUsize::new(0));
+        let mut rev_lines =
+            blocking_rev_lines(&blocked_reads).io_error_policy(IoErrorPolicy::SkipLine);
+        assert_eq!(rev_lines.next().transpose()?, Some("CCC".to_string()));
+
+        // Reading "AAA\n" times out while "BBB" is read, so that line is skipped once the
+        // blocked read was released and undone
+        blocked_reads.store(1, SeqCst);
+        assert_eq!(rev_lines.next().transpose()?, Some("AAA".to_string()));
+        assert_eq!(rev_lines.next().transpose()?, None);
+
+        let lines = RevLines::with_read_timeout(Duration::from_secs(5), Cursor::new("a\nb\n"))
+            .collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(lines, ["b", "a"]);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,289 @@
+//! Async reverse line reading for tokio 1.x readers.
+//!
+//! Enabled with the `tokio1` feature. Lines end in `\n`, and a single `\r` right
+//! before the `\n` is stripped, the same as the default for the sync iterators.
+//!
+//! The lines are scanned by this module itself rather than by [`crate::RawRevLines`],
+//! so none of the options of the sync iterators are supported. There is no other
+//! delimiter or line ending, no handling of `\r` besides the above, and no trailing
+//! newline policy: a single trailing `\n` is always dropped, like
+//! [`crate::TrailingNewlinePolicy::TrimOne`].
+
+use std::io::{self, SeekFrom};
+
+use tokio::io::{AsyncBufRead, AsyncRead, AsyncReadExt, AsyncSeek, AsyncSeekExt, BufReader};
+
+use crate::{RevLinesError, CR_BYTE, DEFAULT_SIZE, LF_BYTE};
+
+/// Reads the lines of an async reader in reverse, last line first.
+///
+/// ```
+/// # tokio::runtime::Builder::new_current_thread().build().unwrap().block_on(async {
+/// use std::io::Cursor;
+///
+/// use rev_lines::tokio1::RevLines;
+///
+/// let mut rev_lines = RevLines::new(Cursor::new("first\nsecond\n"));
+///
+/// assert_eq!(rev_lines.next_line().await.unwrap(), Some("second".to_string()));
+/// assert_eq!(rev_lines.next_line().await.unwrap(), Some("first".to_string()));
+/// assert_eq!(rev_lines.next_line().await.unwrap(), None);
+/// # });
+/// ```
+pub struct RevLines<R> {
+    reader: R,
+    capacity: usize,
+    // Bytes read from the reader but not yet returned, starting at `reader_cursor`
+    buffer: Vec<u8>,
+    // Chunks of the line being read which were read before `buffer`, last chunk first
+    partial: Vec<Vec<u8>>,
+    reader_cursor: u64,
+    initialized: bool,
+    // Whether the bytes at the end of `buffer` were followed by a line feed
+    terminated: bool,
+    // Whether the first line of the reader was returned
+    exhausted: bool,
+}
+
+impl<R: AsyncRead + AsyncSeek + Unpin> RevLines<BufReader<R>> {
+    /// Create a new `RevLines` from an async reader, wrapping it in a tokio `BufReader`.
+    /// Internal buffering for iteration will default to 4096 bytes at a time.
+    pub fn new(reader: R) -> RevLines<BufReader<R>> {
+        RevLines::with_capacity(DEFAULT_SIZE, reader)
+    }
+
+    /// Create a new `RevLines` from an async reader, wrapping it in a tokio `BufReader`.
+    /// Internal buffering for iteration will use `cap` bytes at a time.
+    pub fn with_capacity(cap: usize, reader: R) -> RevLines<BufReader<R>> {
+        RevLines::from_buf_reader_with_capacity(cap, BufReader::with_capacity(cap, reader))
+    }
+}
+
+impl<R: AsyncBufRead + AsyncSeek + Unpin> RevLines<R> {
+    /// Create a new `RevLines` from a reader which is already buffered, such as a
+    /// tokio `BufReader`, so it isn't wrapped in a second buffer.
+    ///
+    /// Every read is preceded by an absolute seek, so whatever the reader has buffered
+    /// is discarded rather than mixed into the lines.
+    pub fn from_buf_reader(reader: R) -> RevLines<R> {
+        RevLines::from_buf_reader_with_capacity(DEFAULT_SIZE, reader)
+    }
+
+    /// Like [`RevLines::from_buf_reader`], reading `cap` bytes at a time.
+    pub fn from_buf_reader_with_capacity(cap: usize, reader: R) -> RevLines<R> {
+        RevLines {
+            reader,
+            capacity: cap.max(1),
+            buffer: Vec::new(),
+            partial: Vec::new(),
+            reader_cursor: 0,
+            initialized: false,
+            terminated: false,
+            exhausted: false,
+        }
+    }
+}
+
+impl<R: AsyncRead + AsyncSeek + Unpin> RevLines<R> {
+    /// Read the next line, moving towards the start of the reader. Returns `None`
+    /// once every line was read.
+    pub async fn next_line(&mut self) -> Result<Option<String>, RevLinesError> {
+        match self.next_line_at().await? {
+            Some((offset, line)) => match String::from_utf8(line) {
+                Ok(line) => Ok(Some(line)),
+                Err(source) => Err(RevLinesError::InvalidUtf8 { offset, source }),
+            },
+            None => Ok(None),
+        }
+    }
+
+    /// Read the next line as raw bytes, like the sync `RawRevLines`, without requiring
+    /// the line to be valid UTF-8.
+    ///
+    /// Like [`RevLines::next_line`] this follows the convention of tokio's
+    /// [`Lines::next_line`](tokio::io::Lines::next_line) and returns `io::Result<Option<_>>`
+    /// rather than the `Option<io::Result<_>>` of an iterator, so `?` can be applied
+    /// before matching on the end of the reader.
+    pub async fn next_line_bytes(&mut self) -> io::Result<Option<Vec<u8>>> {
+        Ok(self.next_line_at().await?.map(|(_, line)| line))
+    }
+
+    /// Read the next line along with the absolute offset of its first byte
+    async fn next_line_at(&mut self) -> io::Result<Option<(u64, Vec<u8>)>> {
+        if !self.initialized {
+            self.initialized = true;
+            self.reader_cursor = self.reader.seek(SeekFrom::End(0)).await?;
+            self.read_to_buffer().await?;
+
+            // Skip the trailing line feed so the first line isn't Some("")
+            if self.buffer.last() == Some(&LF_BYTE) {
+                self.buffer.pop();
+                self.terminated = true;
+            }
+        }
+
+        loop {
+            if let Some(index) = self.buffer.iter().rposition(|byte| *byte == LF_BYTE) {
+                let offset = self.reader_cursor + index as u64 + 1;
+                let line = self.buffer.split_off(index + 1);
+                self.buffer.pop();
+                return Ok(Some((offset, self.finish_line(line))));
+            }
+
+            if self.reader_cursor == 0 {
+                // A line feed always ends a line, even an empty one at the start of the reader
+                let empty = self.buffer.is_empty() && self.partial.is_empty();
+                if self.exhausted || (empty && !self.terminated) {
+                    return Ok(None);
+                }
+
+                self.exhausted = true;
+
+                let line = std::mem::take(&mut self.buffer);
+                return Ok(Some((0, self.finish_line(line))));
+            }
+
+            // Without a line feed the whole buffer is part of the line, so set it aside
+            // instead of copying it along with every chunk read in front of it
+            let chunk = std::mem::take(&mut self.buffer);
+            if !chunk.is_empty() {
+                self.partial.push(chunk);
+            }
+            self.read_to_buffer().await?;
+        }
+    }
+
+    /// Join `line` with the chunks set aside after it, and strip the carriage return of
+    /// a `\r\n` terminator
+    fn finish_line(&mut self, mut line: Vec<u8>) -> Vec<u8> {
+        if !self.partial.is_empty() {
+            line.reserve_exact(self.partial.iter().map(Vec::len).sum());
+            for chunk in self.partial.drain(..).rev() {
+                line.extend_from_slice(&chunk);
+            }
+        }
+
+        if self.terminated && line.last() == Some(&CR_BYTE) {
+            line.pop();
+        }
+
+        self.terminated = true;
+        line
+    }
+
+    /// Read the chunk in front of the bytes read so far into the empty buffer
+    async fn read_to_buffer(&mut self) -> io::Result<()> {
+        let read_len = self.capacity.min(self.reader_cursor as usize);
+
+        self.reader_cursor -= read_len as u64;
+        self.buffer.resize(read_len, 0);
+
+        self.reader
+            .seek(SeekFrom::Start(self.reader_cursor))
+            .await?;
+        self.reader.read_exact(&mut self.buffer).await?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use tokio::io::{AsyncReadExt, BufReader};
+
+    use crate::tokio1::RevLines;
+
+    type TestResult = Result<(), Box<dyn std::error::Error>>;
+
+    async fn collect<R>(mut rev_lines: RevLines<R>) -> Result<Vec<String>, crate::RevLinesError>
+    where
+        R: tokio::io::AsyncRead + tokio::io::AsyncSeek + Unpin,
+    {
+        let mut lines = Vec::new();
+        while let Some(line) = rev_lines.next_line().await? {
+            lines.push(line);
+        }
+        Ok(lines)
+    }
+
+    #[tokio::test]
+    async fn it_handles_lines_across_capacities() -> TestResult {
+        let text = "ABCD\n\nEF\r\nGH\nIJ\n";
+
+        for cap in 1..(text.len() + 1) {
+            let rev_lines = RevLines::with_capacity(cap, Cursor::new(text));
+            assert_eq!(
+                collect(rev_lines).await?,
+                vec!["IJ", "GH", "EF", "", "ABCD"]
+            );
+        }
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn it_handles_long_lines() -> TestResult {
+        let long = "x".repeat(1000);
+        let text = format!("A\n{long}\r\nB{long}\n");
+
+        for cap in [1, 7, 64, 4096] {
+            let rev_lines = RevLines::with_capacity(cap, Cursor::new(&text));
+            assert_eq!(
+                collect(rev_lines).await?,
+                vec![format!("B{long}"), long.clone(), "A".to_string()],
+                "capacity {cap}"
+            );
+        }
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn it_handles_leading_empty_lines() -> TestResult {
+        for cap in 1..4 {
+            let rev_lines = RevLines::with_capacity(cap, Cursor::new("\n"));
+            assert_eq!(collect(rev_lines).await?, vec![""]);
+
+            let rev_lines = RevLines::with_capacity(cap, Cursor::new("\nA"));
+            assert_eq!(collect(rev_lines).await?, vec!["A", ""]);
+
+            let rev_lines = RevLines::with_capacity(cap, Cursor::new(""));
+            assert!(collect(rev_lines).await?.is_empty());
+        }
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn raw_handles_invalid_utf8() -> TestResult {
+        for cap in 1..5 {
+            let file = Cursor::new(b"A\xff\r\n\xc3\n\xe2\x82B\n".to_vec());
+            let mut rev_lines = RevLines::with_capacity(cap, file);
+
+            assert_eq!(
+                rev_lines.next_line_bytes().await?,
+                Some(b"\xe2\x82B".to_vec())
+            );
+            assert_eq!(rev_lines.next_line_bytes().await?, Some(b"\xc3".to_vec()));
+            assert_eq!(rev_lines.next_line_bytes().await?, Some(b"A\xff".to_vec()));
+            assert_eq!(rev_lines.next_line_bytes().await?, None);
+        }
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn it_handles_pre_wrapped_buf_reader() -> TestResult {
+        let mut reader = BufReader::with_capacity(4, Cursor::new("A\nB\nC\nD"));
+        // Leave some bytes in the BufReader's buffer, which seeks must discard
+        let mut first = [0; 1];
+        reader.read_exact(&mut first).await?;
+
+        let rev_lines = RevLines::from_buf_reader_with_capacity(3, reader);
+        assert_eq!(collect(rev_lines).await?, vec!["D", "C", "B", "A"]);
+
+        Ok(())
+    }
+}